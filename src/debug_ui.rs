@@ -0,0 +1,200 @@
+// debug_ui.rs - Optional egui side panel (cargo feature `debug-ui`) with
+// sliders, collapsing sections and a frame-time plot. Values are edited in
+// the same resources the keys change, and toggles press the bound key, so
+// the panel and the Bevy UI overlay always show the same state.
+
+use bevy::input::mouse::{MouseMotion, MouseWheel};
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts, EguiPlugin, EguiPreUpdateSet};
+use std::collections::VecDeque;
+
+use crate::keys::{key_label, Action, KeyBindings};
+use crate::{
+    ui_visible, AnimationState, BatchSize, FpsCounter, FrameTimeStats, GlassMode, HueCycle, Lens, OrbitCamera,
+    SimulationState, SimulationStats, StressConfig, StressMode, TimeScale, BATCH_SIZE_RANGE, FOV_RANGE,
+    PERCENTILE_WINDOW_SECS, TIME_SCALE_STEPS,
+};
+
+/// Frame time the plot marks as the 60 FPS budget.
+const BUDGET_MS: f32 = 1000.0 / 60.0;
+const PLOT_HEIGHT: f32 = 90.0;
+
+pub struct DebugUiPlugin;
+
+impl Plugin for DebugUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(EguiPlugin)
+            .init_resource::<PanelPresses>()
+            .add_systems(
+                PreUpdate,
+                (
+                    press_panel_keys.after(InputSystem),
+                    keep_pointer_on_panel.after(EguiPreUpdateSet::ProcessInput),
+                ),
+            )
+            // Hidden along with the Bevy UI by F1
+            .add_systems(Update, debug_panel.run_if(ui_visible));
+    }
+}
+
+/// Actions the panel clicked this frame, pressed for one frame on the next,
+/// like `ui_buttons_as_keys` does for the Bevy UI buttons.
+#[derive(Resource, Default)]
+struct PanelPresses {
+    queued: Vec<Action>,
+    held: Vec<KeyCode>,
+}
+
+fn press_panel_keys(
+    mut presses: ResMut<PanelPresses>,
+    bindings: Res<KeyBindings>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+) {
+    let PanelPresses { queued, held } = &mut *presses;
+    for key in held.drain(..) {
+        keys.release(key);
+    }
+    for action in queued.drain(..) {
+        let key = bindings.key(action);
+        keys.press(key);
+        held.push(key);
+    }
+}
+
+/// Drags and scrolls over the panel don't reach the orbit or free-fly camera.
+fn keep_pointer_on_panel(
+    mut contexts: EguiContexts,
+    mut mouse: ResMut<ButtonInput<MouseButton>>,
+    mut motion: ResMut<Events<MouseMotion>>,
+    mut wheel: ResMut<Events<MouseWheel>>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    if ctx.wants_pointer_input() || ctx.is_pointer_over_area() {
+        mouse.reset_all();
+        motion.clear();
+        wheel.clear();
+    }
+}
+
+/// A checkbox showing `on`; clicking it presses the action's key rather
+/// than writing the state, so the key system stays the only writer.
+fn toggle(ui: &mut egui::Ui, presses: &mut PanelPresses, bindings: &KeyBindings, label: &str, on: bool, action: Action) {
+    let mut checked = on;
+    if ui.checkbox(&mut checked, format!("{} [{}]", label, key_label(bindings.key(action)))).changed() {
+        presses.queued.push(action);
+    }
+}
+
+fn button(ui: &mut egui::Ui, presses: &mut PanelPresses, bindings: &KeyBindings, label: &str, action: Action) {
+    if ui.button(format!("{} [{}]", label, key_label(bindings.key(action)))).clicked() {
+        presses.queued.push(action);
+    }
+}
+
+/// Frame times over the percentile window, newest on the right, with the
+/// 60 FPS budget as a gray line.
+fn frame_time_plot(ui: &mut egui::Ui, frame_times: &VecDeque<(f32, f32)>) {
+    let (response, painter) = ui.allocate_painter(egui::vec2(ui.available_width(), PLOT_HEIGHT), egui::Sense::hover());
+    let rect = response.rect;
+    painter.rect_filled(rect, 2.0, egui::Color32::from_gray(24));
+    let Some(&(newest, _)) = frame_times.back() else {
+        return;
+    };
+    let max_ms = frame_times.iter().map(|(_, ms)| *ms).fold(BUDGET_MS, f32::max) * 1.1;
+    let y = |ms: f32| rect.bottom() - rect.height() * (ms / max_ms);
+    painter.hline(rect.x_range(), y(BUDGET_MS), egui::Stroke::new(1.0, egui::Color32::from_gray(100)));
+    let points: Vec<egui::Pos2> = frame_times
+        .iter()
+        .map(|&(at, ms)| egui::pos2(rect.right() - rect.width() * (newest - at) / PERCENTILE_WINDOW_SECS, y(ms)))
+        .collect();
+    painter.add(egui::Shape::line(points, egui::Stroke::new(1.0, egui::Color32::LIGHT_GREEN)));
+    painter.text(
+        rect.left_top() + egui::vec2(4.0, 2.0),
+        egui::Align2::LEFT_TOP,
+        format!("{:.1} ms", max_ms),
+        egui::FontId::monospace(10.0),
+        egui::Color32::GRAY,
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+fn debug_panel(
+    mut contexts: EguiContexts,
+    mut presses: ResMut<PanelPresses>,
+    (bindings, config): (Res<KeyBindings>, Res<StressConfig>),
+    (mut batch_size, mut time_scale, mut lens): (ResMut<BatchSize>, ResMut<TimeScale>, ResMut<Lens>),
+    (animation, state): (Res<AnimationState>, Res<State<SimulationState>>),
+    (glass, hue): (Res<GlassMode>, Res<HueCycle>),
+    (stats, fps_counter, percentiles): (Res<SimulationStats>, Res<FpsCounter>, Res<FrameTimeStats>),
+    orbits: Query<&OrbitCamera>,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let presses = &mut *presses;
+    egui::SidePanel::right("debug_ui").default_width(280.0).show(ctx, |ui| {
+        egui::CollapsingHeader::new("Spawn").default_open(true).show(ui, |ui| {
+            ui.label(format!("Entities: {}", stats.total_entities));
+            let mut size = batch_size.0;
+            ui.add(egui::Slider::new(&mut size, BATCH_SIZE_RANGE.0..=BATCH_SIZE_RANGE.1).logarithmic(true).text("batch"));
+            batch_size.set_if_neq(BatchSize(size));
+            ui.horizontal(|ui| {
+                button(ui, presses, &bindings, "Spawn", Action::Spawn);
+                button(ui, presses, &bindings, "Despawn", Action::DespawnBatch);
+                button(ui, presses, &bindings, "Reset", Action::Reset);
+            });
+        });
+
+        egui::CollapsingHeader::new("Simulation").default_open(true).show(ui, |ui| {
+            toggle(ui, presses, &bindings, "Shape animation", animation.shapes, Action::Animation);
+            let paused = *state.get() == SimulationState::Paused;
+            toggle(ui, presses, &bindings, "Frozen", paused, Action::Freeze);
+            let mut scale = time_scale.0;
+            let range = TIME_SCALE_STEPS[0]..=TIME_SCALE_STEPS[TIME_SCALE_STEPS.len() - 1];
+            ui.add(egui::Slider::new(&mut scale, range).logarithmic(true).text("time scale"));
+            time_scale.set_if_neq(TimeScale(scale));
+        });
+
+        egui::CollapsingHeader::new("Materials").default_open(true).show(ui, |ui| {
+            ui.label(format!("Mode: {} ({})", config.materials, config.preset));
+            toggle(ui, presses, &bindings, "Glass", glass.enabled, Action::Glass);
+            button(ui, presses, &bindings, "Next alpha mode", Action::AlphaMode);
+            toggle(ui, presses, &bindings, "Hue cycling", hue.enabled, Action::HueCycle);
+        });
+
+        if config.mode != StressMode::Sprites {
+            egui::CollapsingHeader::new("Camera").default_open(true).show(ui, |ui| {
+                if let Some(orbit) = orbits.iter().next() {
+                    toggle(ui, presses, &bindings, "Orbit paused", orbit.paused, Action::PauseOrbit);
+                    toggle(ui, presses, &bindings, "Auto-frame", orbit.auto_frame, Action::AutoFrame);
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Orbit speed {:.2}", orbit.speed.abs()));
+                        button(ui, presses, &bindings, "Slower", Action::SlowerOrbit);
+                        button(ui, presses, &bindings, "Faster", Action::FasterOrbit);
+                    });
+                }
+                let mut fov = lens.fov_degrees;
+                if ui.add(egui::Slider::new(&mut fov, FOV_RANGE.0..=FOV_RANGE.1).text("FOV °")).changed() {
+                    // Dragging the FOV returns to perspective, as -/= do
+                    lens.fov_degrees = fov;
+                    lens.orthographic = false;
+                }
+                toggle(ui, presses, &bindings, "Orthographic", lens.orthographic, Action::Orthographic);
+            });
+        }
+
+        egui::CollapsingHeader::new("Frame time").default_open(true).show(ui, |ui| {
+            match percentiles.latest {
+                Some(p) => ui.label(format!(
+                    "Last {:.0} s: p50 {:.1} ms  p95 {:.1} ms  p99 {:.1} ms",
+                    p.window_secs, p.p50_ms, p.p95_ms, p.p99_ms
+                )),
+                None => ui.label("Collecting frames..."),
+            };
+            frame_time_plot(ui, &fps_counter.frame_times);
+        });
+    });
+}
@@ -0,0 +1,295 @@
+// export.rs - Writes a spawn mesh to Wavefront OBJ or a self-contained glTF
+// so the generated geometry can be inspected in a DCC tool.
+
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+use serde_json::json;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Output format, picked from the export path's extension.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MeshFormat {
+    Obj,
+    Gltf,
+}
+
+impl MeshFormat {
+    pub fn from_path(path: &str) -> Result<Self, String> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("obj") => Ok(MeshFormat::Obj),
+            Some("gltf") => Ok(MeshFormat::Gltf),
+            _ => Err(format!("Cannot export mesh to {} (expected .obj or .gltf)", path)),
+        }
+    }
+}
+
+/// The attributes both formats write. Normals and UVs are optional; every
+/// mesh this crate builds has them.
+struct MeshData {
+    positions: Vec<[f32; 3]>,
+    normals: Option<Vec<[f32; 3]>>,
+    uvs: Option<Vec<[f32; 2]>>,
+    indices: Vec<u32>,
+}
+
+impl MeshData {
+    fn from_mesh(mesh: &Mesh) -> Result<Self, String> {
+        let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float32x3(positions)) => positions.clone(),
+            _ => return Err("mesh has no Float32x3 positions".to_string()),
+        };
+        let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            Some(VertexAttributeValues::Float32x3(normals)) => Some(normals.clone()),
+            _ => None,
+        };
+        let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float32x2(uvs)) => Some(uvs.clone()),
+            _ => None,
+        };
+        let indices: Vec<u32> = match mesh.indices() {
+            Some(indices) => indices.iter().map(|index| index as u32).collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        if !indices.len().is_multiple_of(3) {
+            return Err("mesh is not a triangle list".to_string());
+        }
+        Ok(MeshData { positions, normals, uvs, indices })
+    }
+}
+
+/// Writes `mesh` as OBJ: one `v`/`vt`/`vn` per vertex and 1-based `f` lines.
+/// OBJ puts the UV origin bottom-left, so `v` is flipped.
+pub fn write_obj(mesh: &Mesh, out: &mut impl Write) -> Result<(), String> {
+    let data = MeshData::from_mesh(mesh)?;
+    write_obj_data(&data, out).map_err(|err| err.to_string())
+}
+
+fn write_obj_data(data: &MeshData, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "# bevy-test spawn mesh: {} vertices, {} triangles", data.positions.len(), data.indices.len() / 3)?;
+    for [x, y, z] in &data.positions {
+        writeln!(out, "v {} {} {}", x, y, z)?;
+    }
+    for [u, v] in data.uvs.iter().flatten() {
+        writeln!(out, "vt {} {}", u, 1.0 - v)?;
+    }
+    for [x, y, z] in data.normals.iter().flatten() {
+        writeln!(out, "vn {} {} {}", x, y, z)?;
+    }
+
+    let corner = |index: u32| {
+        let index = index + 1;
+        match (data.uvs.is_some(), data.normals.is_some()) {
+            (true, true) => format!("{0}/{0}/{0}", index),
+            (true, false) => format!("{0}/{0}", index),
+            (false, true) => format!("{0}//{0}", index),
+            (false, false) => index.to_string(),
+        }
+    };
+    for face in data.indices.chunks_exact(3) {
+        writeln!(out, "f {} {} {}", corner(face[0]), corner(face[1]), corner(face[2]))?;
+    }
+    Ok(())
+}
+
+/// A single-mesh glTF 2.0 document with the buffer embedded as a base64 data URI.
+pub fn gltf_document(mesh: &Mesh) -> Result<serde_json::Value, String> {
+    let data = MeshData::from_mesh(mesh)?;
+    let vertex_count = data.positions.len();
+
+    let mut buffer = Vec::new();
+    let mut views = Vec::new();
+    let mut push_view = |bytes: Vec<u8>, target: u32| {
+        views.push(json!({
+            "buffer": 0,
+            "byteOffset": buffer.len(),
+            "byteLength": bytes.len(),
+            "target": target,
+        }));
+        buffer.extend(bytes);
+        views.len() - 1
+    };
+    const ARRAY_BUFFER: u32 = 34962;
+    const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+    const FLOAT: u32 = 5126;
+    const UNSIGNED_INT: u32 = 5125;
+
+    let (min, max) = data.positions.iter().fold(
+        ([f32::MAX; 3], [f32::MIN; 3]),
+        |(min, max), position| {
+            (
+                [0, 1, 2].map(|axis| min[axis].min(position[axis])),
+                [0, 1, 2].map(|axis| max[axis].max(position[axis])),
+            )
+        },
+    );
+    let positions = push_view(float_bytes(data.positions.iter().flatten()), ARRAY_BUFFER);
+    let mut accessors = vec![json!({
+        "bufferView": positions,
+        "componentType": FLOAT,
+        "count": vertex_count,
+        "type": "VEC3",
+        "min": min,
+        "max": max,
+    })];
+    let mut attributes = serde_json::Map::new();
+    attributes.insert("POSITION".to_string(), json!(0));
+
+    if let Some(normals) = &data.normals {
+        let view = push_view(float_bytes(normals.iter().flatten()), ARRAY_BUFFER);
+        attributes.insert("NORMAL".to_string(), json!(accessors.len()));
+        accessors.push(json!({ "bufferView": view, "componentType": FLOAT, "count": vertex_count, "type": "VEC3" }));
+    }
+    if let Some(uvs) = &data.uvs {
+        let view = push_view(float_bytes(uvs.iter().flatten()), ARRAY_BUFFER);
+        attributes.insert("TEXCOORD_0".to_string(), json!(accessors.len()));
+        accessors.push(json!({ "bufferView": view, "componentType": FLOAT, "count": vertex_count, "type": "VEC2" }));
+    }
+    let indices = push_view(
+        data.indices.iter().flat_map(|index| index.to_le_bytes()).collect(),
+        ELEMENT_ARRAY_BUFFER,
+    );
+    let indices_accessor = accessors.len();
+    accessors.push(json!({
+        "bufferView": indices,
+        "componentType": UNSIGNED_INT,
+        "count": data.indices.len(),
+        "type": "SCALAR",
+    }));
+
+    Ok(json!({
+        "asset": { "version": "2.0", "generator": "bevy-test" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0 }],
+        "meshes": [{
+            "primitives": [{ "attributes": attributes, "indices": indices_accessor, "mode": 4 }],
+        }],
+        "accessors": accessors,
+        "bufferViews": views,
+        "buffers": [{
+            "byteLength": buffer.len(),
+            "uri": format!("data:application/octet-stream;base64,{}", base64(&buffer)),
+        }],
+    }))
+}
+
+/// Writes `mesh` to `path` in the format its extension names.
+pub fn export_mesh(mesh: &Mesh, path: &str) -> Result<(), String> {
+    let bytes = match MeshFormat::from_path(path)? {
+        MeshFormat::Obj => {
+            let mut out = Vec::new();
+            write_obj(mesh, &mut out)?;
+            out
+        }
+        MeshFormat::Gltf => {
+            let document = gltf_document(mesh)?;
+            serde_json::to_vec(&document).map_err(|err| err.to_string())?
+        }
+    };
+    fs::write(path, bytes).map_err(|err| format!("Failed to write {}: {}", path, err))
+}
+
+fn float_bytes<'a>(values: impl Iterator<Item = &'a f32>) -> Vec<u8> {
+    values.flat_map(|value| value.to_le_bytes()).collect()
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let triple = chunk.iter().enumerate().fold(0u32, |acc, (i, &byte)| acc | (byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(triple >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{create_icosahedron_mesh, Shading, VertexNoise};
+
+    fn parse_floats<const N: usize>(line: &str) -> [f32; N] {
+        let values: Vec<f32> = line.split_whitespace().skip(1).map(|value| value.parse().unwrap()).collect();
+        values.try_into().unwrap()
+    }
+
+    #[test]
+    fn obj_round_trips_vertices_and_faces() {
+        let noise = VertexNoise { amplitude: 0.2, seed: 7 };
+        let mesh = create_icosahedron_mesh(1.5, 2, Shading::Smooth, false, noise);
+        let mut out = Vec::new();
+        write_obj(&mesh, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        let (mut positions, mut uvs, mut normals, mut faces) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        for line in text.lines() {
+            match line.split_whitespace().next() {
+                Some("v") => positions.push(parse_floats::<3>(line)),
+                Some("vt") => uvs.push(parse_floats::<2>(line)),
+                Some("vn") => normals.push(parse_floats::<3>(line)),
+                Some("f") => faces.push(line.to_string()),
+                _ => {}
+            }
+        }
+
+        let data = MeshData::from_mesh(&mesh).unwrap();
+        assert_eq!(positions.len(), mesh.count_vertices());
+        assert_eq!(uvs.len(), mesh.count_vertices());
+        assert_eq!(normals.len(), mesh.count_vertices());
+        assert_eq!(faces.len(), data.indices.len() / 3);
+
+        // Shortest round-trip float formatting makes the values exact
+        for i in [0, 17, positions.len() - 1] {
+            assert_eq!(positions[i], data.positions[i]);
+            assert_eq!(normals[i], data.normals.as_ref().unwrap()[i]);
+            let [u, v] = data.uvs.as_ref().unwrap()[i];
+            assert_eq!(uvs[i], [u, 1.0 - v]);
+        }
+        let first: Vec<u32> = faces[0]
+            .split_whitespace()
+            .skip(1)
+            .map(|corner| corner.split('/').next().unwrap().parse::<u32>().unwrap() - 1)
+            .collect();
+        assert_eq!(first, data.indices[..3]);
+    }
+
+    #[test]
+    fn gltf_buffer_matches_accessors() {
+        let mesh = create_icosahedron_mesh(1.0, 1, Shading::Flat, false, VertexNoise::NONE);
+        let document = gltf_document(&mesh).unwrap();
+        let vertices = mesh.count_vertices() as u64;
+
+        let accessors = document["accessors"].as_array().unwrap();
+        assert_eq!(accessors.len(), 4);
+        assert_eq!(accessors[0]["count"], vertices);
+        assert_eq!(accessors[3]["count"], 80 * 3);
+
+        // 3 + 3 + 2 floats per vertex, then u32 indices
+        let byte_length = document["buffers"][0]["byteLength"].as_u64().unwrap();
+        assert_eq!(byte_length, vertices * 8 * 4 + 80 * 3 * 4);
+        let uri = document["buffers"][0]["uri"].as_str().unwrap();
+        let encoded = uri.strip_prefix("data:application/octet-stream;base64,").unwrap();
+        assert_eq!(encoded.len() as u64, byte_length.div_ceil(3) * 4);
+    }
+
+    #[test]
+    fn base64_pads_partial_chunks() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+}
@@ -0,0 +1,300 @@
+// instancing.rs - Experimental hand-rolled instanced draw for `--instancing`
+// (cargo feature `instancing`), after Bevy's shader_instancing example. Each
+// entity carries the instances of one mesh; their transforms and colors are
+// extracted every frame into a per-instance vertex buffer and drawn with a
+// single instanced call in the transparent 3D phase. Pins to render internals,
+// so expect it to break on Bevy upgrades.
+
+use bevy::asset::load_internal_asset;
+use bevy::core_pipeline::core_3d::Transparent3d;
+use bevy::core_pipeline::prepass::{DeferredPrepass, DepthPrepass, MotionVectorPrepass, NormalPrepass};
+use bevy::ecs::query::QueryItem;
+use bevy::ecs::system::lifetimeless::{Read, SRes};
+use bevy::ecs::system::SystemParamItem;
+use bevy::pbr::{MeshPipeline, MeshPipelineKey, RenderMeshInstances, SetMeshBindGroup, SetMeshViewBindGroup};
+use bevy::prelude::*;
+use bevy::render::extract_component::{ExtractComponent, ExtractComponentPlugin};
+use bevy::render::mesh::allocator::MeshAllocator;
+use bevy::render::mesh::{MeshVertexBufferLayoutRef, RenderMesh, RenderMeshBufferInfo};
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_phase::{
+    AddRenderCommand, DrawFunctions, PhaseItem, PhaseItemExtraIndex, RenderCommand, RenderCommandResult,
+    SetItemPipeline, TrackedRenderPass, ViewSortedRenderPhases,
+};
+use bevy::render::render_resource::{
+    Buffer, BufferInitDescriptor, BufferUsages, PipelineCache, RenderPipelineDescriptor, SpecializedMeshPipeline,
+    SpecializedMeshPipelineError, SpecializedMeshPipelines, VertexAttribute, VertexBufferLayout, VertexFormat,
+    VertexStepMode,
+};
+use bevy::render::renderer::RenderDevice;
+use bevy::render::sync_world::MainEntity;
+use bevy::render::view::ExtractedView;
+use bevy::render::{Render, RenderApp, RenderSet};
+
+use crate::TimeScale;
+
+const INSTANCING_SHADER: Handle<Shader> = Handle::weak_from_u128(0x6a1c_37e2_94d8_4b0f_a5c3_1e7d_20b9_f468);
+
+/// Floats per instance: position (3), rotation quaternion (4), linear color (4).
+const INSTANCE_FLOATS: usize = 11;
+
+/// One shape drawn by the instanced path.
+#[derive(Clone, Copy)]
+pub struct ShapeInstance {
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub color: LinearRgba,
+}
+
+/// The instances of one mesh in one batch. The entity's own `Mesh3d` is the
+/// shared geometry; its transform stays at the origin.
+#[derive(Component, Clone)]
+pub struct InstancedShapes {
+    pub instances: Vec<ShapeInstance>,
+    pub rotation_speed: f32,
+    /// Floor-pattern shapes only spin about Y, as in `animate_shapes_parallel`.
+    pub grounded: bool,
+}
+
+impl ExtractComponent for InstancedShapes {
+    type QueryData = &'static InstancedShapes;
+    type QueryFilter = ();
+    type Out = Self;
+
+    fn extract_component(item: QueryItem<'_, Self::QueryData>) -> Option<Self> {
+        Some(item.clone())
+    }
+}
+
+/// Applies the same per-frame rotation as `animate_shapes_parallel`,
+/// `TimeScale` included, to every instance, so both paths animate identically.
+pub fn animate_instances(mut query: Query<&mut InstancedShapes>, time: Res<Time>, time_scale: Res<TimeScale>) {
+    let delta_seconds = time.delta_secs() * time_scale.0;
+
+    query.par_iter_mut().for_each(|mut shapes| {
+        let speed = shapes.rotation_speed;
+        let mut spin = Quat::from_rotation_y(delta_seconds * 0.8 * speed);
+        if !shapes.grounded {
+            spin = Quat::from_rotation_x(delta_seconds * 0.5 * speed) * spin;
+        }
+        for instance in &mut shapes.instances {
+            instance.rotation = spin * instance.rotation;
+        }
+    });
+}
+
+pub struct InstancingPlugin;
+
+impl Plugin for InstancingPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, INSTANCING_SHADER, "instancing.wgsl", Shader::from_wgsl);
+        app.add_plugins(ExtractComponentPlugin::<InstancedShapes>::default());
+        app.sub_app_mut(RenderApp)
+            .add_render_command::<Transparent3d, DrawInstanced>()
+            .init_resource::<SpecializedMeshPipelines<InstancingPipeline>>()
+            .add_systems(
+                Render,
+                (
+                    queue_instanced.in_set(RenderSet::QueueMeshes),
+                    prepare_instance_buffers.in_set(RenderSet::PrepareResources),
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        app.sub_app_mut(RenderApp).init_resource::<InstancingPipeline>();
+    }
+}
+
+/// The prepasses a camera runs; the view bind group layout depends on them.
+type ViewPrepasses = (Has<DepthPrepass>, Has<NormalPrepass>, Has<MotionVectorPrepass>, Has<DeferredPrepass>);
+
+#[allow(clippy::too_many_arguments)]
+fn queue_instanced(
+    draw_functions: Res<DrawFunctions<Transparent3d>>,
+    instancing_pipeline: Res<InstancingPipeline>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<InstancingPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    meshes: Res<RenderAssets<RenderMesh>>,
+    render_mesh_instances: Res<RenderMeshInstances>,
+    instanced: Query<(Entity, &MainEntity), With<InstancedShapes>>,
+    mut phases: ResMut<ViewSortedRenderPhases<Transparent3d>>,
+    views: Query<(Entity, &ExtractedView, &Msaa, ViewPrepasses)>,
+) {
+    let draw_instanced = draw_functions.read().id::<DrawInstanced>();
+
+    for (view_entity, view, msaa, (depth, normal, motion_vectors, deferred)) in &views {
+        let Some(phase) = phases.get_mut(&view_entity) else {
+            continue;
+        };
+
+        let mut view_key = MeshPipelineKey::from_msaa_samples(msaa.samples()) | MeshPipelineKey::from_hdr(view.hdr);
+        for (enabled, flag) in [
+            (depth, MeshPipelineKey::DEPTH_PREPASS),
+            (normal, MeshPipelineKey::NORMAL_PREPASS),
+            (motion_vectors, MeshPipelineKey::MOTION_VECTOR_PREPASS),
+            (deferred, MeshPipelineKey::DEFERRED_PREPASS),
+        ] {
+            if enabled {
+                view_key |= flag;
+            }
+        }
+
+        let rangefinder = view.rangefinder3d();
+        for (entity, main_entity) in &instanced {
+            let Some(mesh_instance) = render_mesh_instances.render_mesh_queue_data(*main_entity) else {
+                continue;
+            };
+            let Some(mesh) = meshes.get(mesh_instance.mesh_asset_id) else {
+                continue;
+            };
+            let key = view_key | MeshPipelineKey::from_primitive_topology(mesh.primitive_topology());
+            let pipeline = match pipelines.specialize(&pipeline_cache, &instancing_pipeline, key, &mesh.layout) {
+                Ok(pipeline) => pipeline,
+                Err(err) => {
+                    error!("Instanced pipeline specialization failed: {}", err);
+                    continue;
+                }
+            };
+            phase.add(Transparent3d {
+                entity: (entity, *main_entity),
+                pipeline,
+                draw_function: draw_instanced,
+                distance: rangefinder.distance_translation(&mesh_instance.translation),
+                batch_range: 0..1,
+                extra_index: PhaseItemExtraIndex::NONE,
+            });
+        }
+    }
+}
+
+#[derive(Component)]
+struct InstanceBuffer {
+    buffer: Buffer,
+    length: usize,
+}
+
+/// Re-uploads every entity's instances each frame; that per-frame copy is
+/// part of what the instanced path is measured on.
+fn prepare_instance_buffers(
+    mut commands: Commands,
+    query: Query<(Entity, &InstancedShapes)>,
+    render_device: Res<RenderDevice>,
+) {
+    for (entity, shapes) in &query {
+        let mut contents = Vec::with_capacity(shapes.instances.len() * INSTANCE_FLOATS * 4);
+        for instance in &shapes.instances {
+            let floats = instance
+                .position
+                .to_array()
+                .into_iter()
+                .chain(instance.rotation.to_array())
+                .chain(instance.color.to_f32_array());
+            contents.extend(floats.flat_map(f32::to_le_bytes));
+        }
+        let buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("instanced shapes buffer"),
+            contents: &contents,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        });
+        commands.entity(entity).insert(InstanceBuffer { buffer, length: shapes.instances.len() });
+    }
+}
+
+#[derive(Resource)]
+struct InstancingPipeline {
+    mesh_pipeline: MeshPipeline,
+}
+
+impl FromWorld for InstancingPipeline {
+    fn from_world(world: &mut World) -> Self {
+        InstancingPipeline { mesh_pipeline: world.resource::<MeshPipeline>().clone() }
+    }
+}
+
+impl SpecializedMeshPipeline for InstancingPipeline {
+    type Key = MeshPipelineKey;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayoutRef,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut descriptor = self.mesh_pipeline.specialize(key, layout)?;
+
+        descriptor.vertex.shader = INSTANCING_SHADER;
+        let attribute = |format: VertexFormat, offset: u64, shader_location: u32| VertexAttribute {
+            format,
+            offset,
+            shader_location,
+        };
+        descriptor.vertex.buffers.push(VertexBufferLayout {
+            array_stride: (INSTANCE_FLOATS * 4) as u64,
+            step_mode: VertexStepMode::Instance,
+            attributes: vec![
+                attribute(VertexFormat::Float32x3, 0, 8),
+                attribute(VertexFormat::Float32x4, 12, 9),
+                attribute(VertexFormat::Float32x4, 28, 10),
+            ],
+        });
+        descriptor.fragment.as_mut().unwrap().shader = INSTANCING_SHADER;
+        Ok(descriptor)
+    }
+}
+
+type DrawInstanced = (SetItemPipeline, SetMeshViewBindGroup<0>, SetMeshBindGroup<1>, DrawMeshInstanced);
+
+struct DrawMeshInstanced;
+
+impl<P: PhaseItem> RenderCommand<P> for DrawMeshInstanced {
+    type Param = (SRes<RenderAssets<RenderMesh>>, SRes<RenderMeshInstances>, SRes<MeshAllocator>);
+    type ViewQuery = ();
+    type ItemQuery = Read<InstanceBuffer>;
+
+    #[inline]
+    fn render<'w>(
+        item: &P,
+        _view: (),
+        instance_buffer: Option<&'w InstanceBuffer>,
+        (meshes, render_mesh_instances, mesh_allocator): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        // Borrow the allocator for 'w rather than the param's lifetime
+        let mesh_allocator = mesh_allocator.into_inner();
+
+        let Some(mesh_instance) = render_mesh_instances.render_mesh_queue_data(item.main_entity()) else {
+            return RenderCommandResult::Skip;
+        };
+        let Some(gpu_mesh) = meshes.into_inner().get(mesh_instance.mesh_asset_id) else {
+            return RenderCommandResult::Skip;
+        };
+        let Some(instance_buffer) = instance_buffer else {
+            return RenderCommandResult::Skip;
+        };
+        let Some(vertex_buffer_slice) = mesh_allocator.mesh_vertex_slice(&mesh_instance.mesh_asset_id) else {
+            return RenderCommandResult::Skip;
+        };
+
+        pass.set_vertex_buffer(0, vertex_buffer_slice.buffer.slice(..));
+        pass.set_vertex_buffer(1, instance_buffer.buffer.slice(..));
+
+        let instances = 0..instance_buffer.length as u32;
+        match &gpu_mesh.buffer_info {
+            RenderMeshBufferInfo::Indexed { index_format, count } => {
+                let Some(index_buffer_slice) = mesh_allocator.mesh_index_slice(&mesh_instance.mesh_asset_id) else {
+                    return RenderCommandResult::Skip;
+                };
+                pass.set_index_buffer(index_buffer_slice.buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(
+                    index_buffer_slice.range.start..(index_buffer_slice.range.start + count),
+                    vertex_buffer_slice.range.start as i32,
+                    instances,
+                );
+            }
+            RenderMeshBufferInfo::NonIndexed => {
+                pass.draw(vertex_buffer_slice.range, instances);
+            }
+        }
+        RenderCommandResult::Success
+    }
+}
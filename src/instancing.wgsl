@@ -0,0 +1,47 @@
+// instancing.wgsl - Vertex/fragment shader for the `--instancing` draw: every
+// instance carries its own position, rotation and color. Lighting is a fixed
+// sun plus ambient term rather than the PBR pipeline.
+
+#import bevy_pbr::mesh_view_bindings::view
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+
+    // Past the mesh attribute locations (0-7), so any mesh layout fits
+    @location(8) i_position: vec3<f32>,
+    @location(9) i_rotation: vec4<f32>,
+    @location(10) i_color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) normal: vec3<f32>,
+};
+
+// Toward the scene's sun at (50, 80, 50)
+const SUN_DIRECTION: vec3<f32> = vec3<f32>(0.47, 0.75, 0.47);
+const AMBIENT: f32 = 0.25;
+
+fn rotate(q: vec4<f32>, v: vec3<f32>) -> vec3<f32> {
+    let t = 2.0 * cross(q.xyz, v);
+    return v + q.w * t + cross(q.xyz, t);
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let world = rotate(vertex.i_rotation, vertex.position) + vertex.i_position;
+
+    var out: VertexOutput;
+    out.clip_position = view.clip_from_world * vec4<f32>(world, 1.0);
+    out.color = vertex.i_color;
+    out.normal = rotate(vertex.i_rotation, vertex.normal);
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let diffuse = max(dot(normalize(in.normal), SUN_DIRECTION), 0.0);
+    return vec4<f32>(in.color.rgb * (AMBIENT + diffuse), in.color.a);
+}
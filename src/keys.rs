@@ -0,0 +1,354 @@
+// keys.rs - The key bindings table: every keyboard action with its default
+// key and help text, remappable with `--bind ACTION=KEY`. Input systems, the
+// help panel and the startup banner all read it.
+
+use bevy::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    Spawn,
+    SmallerBatch,
+    LargerBatch,
+    SpawnLights,
+    BatchPanel,
+    Reset,
+    DespawnBatch,
+    Animation,
+    Freeze,
+    SlowMotion,
+    FastForward,
+    Merge,
+    Wireframe,
+    Bounds,
+    Glass,
+    AlphaMode,
+    HueCycle,
+    Bloom,
+    Ssao,
+    Tonemapping,
+    Msaa,
+    Transmission,
+    Fog,
+    Darker,
+    Brighter,
+    Reflections,
+    Skybox,
+    Background,
+    PauseOrbit,
+    SlowerOrbit,
+    FasterOrbit,
+    AutoFrame,
+    Follow,
+    Chase,
+    ExitChase,
+    NarrowerFov,
+    WiderFov,
+    Orthographic,
+    FreeFly,
+    FlyForward,
+    FlyBack,
+    FlyLeft,
+    FlyRight,
+    FlyUp,
+    FlyDown,
+    SunShadows,
+    ShapeShadows,
+    Cascades,
+    ShadowMapSize,
+    ExportMesh,
+    HideUi,
+    SmallerUi,
+    LargerUi,
+    Console,
+    Percentiles,
+    Help,
+    Screenshot,
+    Capture,
+}
+
+/// One row of the table.
+#[derive(Clone, Copy, Debug)]
+pub struct Binding {
+    pub action: Action,
+    /// The `--bind` name.
+    pub name: &'static str,
+    pub key: KeyCode,
+    pub description: &'static str,
+    /// Only read while free-flying, so it may share a key with an `orbit` action.
+    pub fly: bool,
+    /// Its systems don't run while free-flying, so a `fly` action may share its key.
+    pub orbit: bool,
+}
+
+impl Binding {
+    /// Whether the two are read at the same time, so can't share a key.
+    fn clashes_with(&self, other: &Binding) -> bool {
+        !(self.fly && other.orbit || self.orbit && other.fly)
+    }
+}
+
+const fn bind(action: Action, name: &'static str, key: KeyCode, description: &'static str) -> Binding {
+    Binding { action, name, key, description, fly: false, orbit: false }
+}
+
+const fn orbit(action: Action, name: &'static str, key: KeyCode, description: &'static str) -> Binding {
+    Binding { action, name, key, description, fly: false, orbit: true }
+}
+
+const fn fly(action: Action, name: &'static str, key: KeyCode, description: &'static str) -> Binding {
+    Binding { action, name, key, description, fly: true, orbit: false }
+}
+
+/// The defaults, in help-panel order.
+pub const DEFAULT_BINDINGS: [Binding; 58] = [
+    bind(Action::Spawn, "spawn", KeyCode::Space, "Spawn a Batch of Shapes"),
+    bind(Action::SmallerBatch, "smaller-batch", KeyCode::PageDown, "Halve the Batch Size"),
+    bind(Action::LargerBatch, "larger-batch", KeyCode::PageUp, "Double the Batch Size"),
+    bind(Action::SpawnLights, "spawn-lights", KeyCode::KeyL, "Spawn 100 Point Lights"),
+    bind(Action::BatchPanel, "batch-panel", KeyCode::KeyI, "Toggle Batch Panel"),
+    bind(Action::Reset, "reset", KeyCode::KeyR, "Reset Scene"),
+    bind(Action::DespawnBatch, "despawn-batch", KeyCode::Backspace, "Despawn Newest Batch"),
+    bind(Action::Animation, "animation", KeyCode::KeyN, "Toggle Shape Animation"),
+    bind(Action::Freeze, "freeze", KeyCode::F5, "Freeze Animation"),
+    bind(Action::SlowMotion, "slow-motion", KeyCode::Semicolon, "Slow Motion"),
+    bind(Action::FastForward, "fast-forward", KeyCode::Quote, "Fast Forward"),
+    bind(Action::Merge, "merge", KeyCode::KeyM, "Toggle Merged Mesh"),
+    orbit(Action::Wireframe, "wireframe", KeyCode::KeyW, "Toggle Wireframe"),
+    bind(Action::Bounds, "bounds", KeyCode::KeyB, "Toggle Bounds Overlay"),
+    orbit(Action::Glass, "glass", KeyCode::KeyT, "Toggle Glass Materials"),
+    orbit(Action::AlphaMode, "alpha-mode", KeyCode::KeyA, "Cycle Glass Alpha Mode"),
+    bind(Action::HueCycle, "hue-cycle", KeyCode::KeyH, "Toggle Hue Cycling"),
+    bind(Action::Bloom, "bloom", KeyCode::F2, "Toggle Bloom"),
+    bind(Action::Ssao, "ssao", KeyCode::F3, "Toggle SSAO"),
+    bind(Action::Tonemapping, "tonemapping", KeyCode::F4, "Cycle Tonemapping"),
+    bind(Action::Msaa, "msaa", KeyCode::F6, "Cycle MSAA"),
+    bind(Action::Transmission, "transmission", KeyCode::F7, "Cycle Transmission Quality"),
+    bind(Action::Fog, "fog", KeyCode::F8, "Toggle Distance Fog"),
+    bind(Action::Darker, "darker", KeyCode::BracketLeft, "Darker Exposure"),
+    bind(Action::Brighter, "brighter", KeyCode::BracketRight, "Brighter Exposure"),
+    bind(Action::Reflections, "reflections", KeyCode::KeyF, "Toggle Floor Reflections"),
+    bind(Action::Skybox, "skybox", KeyCode::KeyK, "Toggle Skybox"),
+    bind(Action::Background, "background", KeyCode::KeyG, "Cycle Background"),
+    orbit(Action::PauseOrbit, "pause-orbit", KeyCode::KeyV, "Pause Orbit"),
+    orbit(Action::SlowerOrbit, "slower-orbit", KeyCode::Comma, "Slower Orbit"),
+    orbit(Action::FasterOrbit, "faster-orbit", KeyCode::Period, "Faster Orbit"),
+    orbit(Action::AutoFrame, "auto-frame", KeyCode::KeyZ, "Auto-Frame Shapes"),
+    orbit(Action::Follow, "follow", KeyCode::KeyJ, "Follow New Batches"),
+    orbit(Action::Chase, "chase", KeyCode::KeyY, "Chase Random Shape"),
+    orbit(Action::ExitChase, "exit-chase", KeyCode::Escape, "Back to the Orbit"),
+    bind(Action::NarrowerFov, "narrower-fov", KeyCode::Minus, "Narrower FOV"),
+    bind(Action::WiderFov, "wider-fov", KeyCode::Equal, "Wider FOV"),
+    bind(Action::Orthographic, "orthographic", KeyCode::KeyU, "Toggle Orthographic"),
+    bind(Action::FreeFly, "free-fly", KeyCode::Tab, "Orbit / Free-Fly Camera"),
+    fly(Action::FlyForward, "fly-forward", KeyCode::KeyW, "Fly Forward"),
+    fly(Action::FlyBack, "fly-back", KeyCode::KeyS, "Fly Back"),
+    fly(Action::FlyLeft, "fly-left", KeyCode::KeyA, "Fly Left"),
+    fly(Action::FlyRight, "fly-right", KeyCode::KeyD, "Fly Right"),
+    fly(Action::FlyUp, "fly-up", KeyCode::KeyE, "Fly Up"),
+    fly(Action::FlyDown, "fly-down", KeyCode::KeyQ, "Fly Down"),
+    orbit(Action::SunShadows, "sun-shadows", KeyCode::KeyS, "Toggle Sun Shadows"),
+    bind(Action::ShapeShadows, "shape-shadows", KeyCode::KeyC, "Toggle Shape Shadows"),
+    orbit(Action::Cascades, "cascades", KeyCode::KeyO, "Cycle Shadow Cascades"),
+    orbit(Action::ShadowMapSize, "shadow-map-size", KeyCode::KeyP, "Cycle Shadow Map Size"),
+    bind(Action::ExportMesh, "export-mesh", KeyCode::KeyX, "Export Spawn Mesh"),
+    bind(Action::HideUi, "hide-ui", KeyCode::F1, "Hide UI"),
+    bind(Action::SmallerUi, "smaller-ui", KeyCode::F10, "Smaller UI"),
+    bind(Action::LargerUi, "larger-ui", KeyCode::F11, "Larger UI"),
+    bind(Action::Console, "console", KeyCode::Backquote, "Log Console"),
+    bind(Action::Percentiles, "percentiles", KeyCode::Backslash, "Frame-Time Percentiles"),
+    bind(Action::Help, "help", KeyCode::Slash, "Toggle This Panel"),
+    bind(Action::Screenshot, "screenshot", KeyCode::F12, "Screenshot (Shift: Without UI)"),
+    bind(Action::Capture, "capture", KeyCode::F9, "Capture Frame Sequence"),
+];
+
+/// The bindings in effect: the defaults with any `--bind` overrides applied.
+#[derive(Resource, Clone, Debug)]
+pub struct KeyBindings {
+    bindings: Vec<Binding>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings { bindings: DEFAULT_BINDINGS.to_vec() }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, action: Action) -> KeyCode {
+        self.bindings
+            .iter()
+            .find(|binding| binding.action == action)
+            .expect("every action has a binding")
+            .key
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Binding> {
+        self.bindings.iter()
+    }
+
+    /// Applies one `ACTION=KEY` override. A key already taken by another
+    /// action is an error, except between a free-fly action and one whose
+    /// systems only run while orbiting.
+    pub fn rebind(&mut self, spec: &str) -> Result<(), String> {
+        let (name, key_name) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid binding: {} (expected ACTION=KEY, e.g. spawn=enter)", spec))?;
+        let key = parse_key(key_name).ok_or_else(|| format!("Unknown key: {}", key_name))?;
+        let index = self
+            .bindings
+            .iter()
+            .position(|binding| binding.name == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = self.bindings.iter().map(|binding| binding.name).collect();
+                format!("Unknown action: {} (expected one of {})", name, names.join(", "))
+            })?;
+        let rebound = self.bindings[index];
+        if let Some(taken) = self
+            .bindings
+            .iter()
+            .enumerate()
+            .find(|(other, binding)| *other != index && binding.key == key && binding.clashes_with(&rebound))
+        {
+            return Err(format!("Cannot bind {} to {}: already bound to {}", name, key_label(key), taken.1.name));
+        }
+        self.bindings[index].key = key;
+        Ok(())
+    }
+}
+
+const LETTER_KEYS: [KeyCode; 26] = [
+    KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF, KeyCode::KeyG,
+    KeyCode::KeyH, KeyCode::KeyI, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL, KeyCode::KeyM, KeyCode::KeyN,
+    KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR, KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU,
+    KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX, KeyCode::KeyY, KeyCode::KeyZ,
+];
+
+const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+    KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+];
+
+const FUNCTION_KEYS: [KeyCode; 12] = [
+    KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+    KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+];
+
+/// `--bind` name and on-screen label of every other bindable key.
+const NAMED_KEYS: [(&str, &str, KeyCode); 22] = [
+    ("space", "SPACE", KeyCode::Space),
+    ("tab", "TAB", KeyCode::Tab),
+    ("enter", "ENTER", KeyCode::Enter),
+    ("escape", "ESC", KeyCode::Escape),
+    ("backspace", "BACKSPACE", KeyCode::Backspace),
+    ("delete", "DEL", KeyCode::Delete),
+    ("insert", "INS", KeyCode::Insert),
+    ("home", "HOME", KeyCode::Home),
+    ("end", "END", KeyCode::End),
+    ("pageup", "PGUP", KeyCode::PageUp),
+    ("pagedown", "PGDN", KeyCode::PageDown),
+    ("minus", "-", KeyCode::Minus),
+    ("equal", "=", KeyCode::Equal),
+    ("comma", ",", KeyCode::Comma),
+    ("period", ".", KeyCode::Period),
+    ("semicolon", ";", KeyCode::Semicolon),
+    ("quote", "'", KeyCode::Quote),
+    ("backquote", "~", KeyCode::Backquote),
+    ("slash", "/", KeyCode::Slash),
+    ("backslash", "\\", KeyCode::Backslash),
+    ("bracketleft", "[", KeyCode::BracketLeft),
+    ("bracketright", "]", KeyCode::BracketRight),
+];
+
+/// Parses a key name: a letter, a digit, `f1`-`f12` or one of `NAMED_KEYS`,
+/// case-insensitively.
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let name = name.to_ascii_lowercase();
+    if let [byte] = name.as_bytes() {
+        match byte {
+            b'a'..=b'z' => return Some(LETTER_KEYS[(byte - b'a') as usize]),
+            b'0'..=b'9' => return Some(DIGIT_KEYS[(byte - b'0') as usize]),
+            _ => {}
+        }
+    }
+    if let Some(number) = name.strip_prefix('f').and_then(|number| number.parse::<usize>().ok()) {
+        return FUNCTION_KEYS.get(number.checked_sub(1)?).copied();
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(key_name, _, _)| *key_name == name)
+        .map(|(_, _, key)| *key)
+}
+
+/// The key as the help panel shows it, e.g. `SPACE`, `F5` or `[`.
+pub fn key_label(key: KeyCode) -> String {
+    if let Some(index) = LETTER_KEYS.iter().position(|&letter| letter == key) {
+        return ((b'A' + index as u8) as char).to_string();
+    }
+    if let Some(index) = DIGIT_KEYS.iter().position(|&digit| digit == key) {
+        return index.to_string();
+    }
+    if let Some(index) = FUNCTION_KEYS.iter().position(|&function| function == key) {
+        return format!("F{}", index + 1);
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(_, _, named)| *named == key)
+        .map_or_else(|| format!("{:?}", key), |(_, label, _)| label.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn default_bindings_are_unique() {
+        let names: HashSet<&str> = DEFAULT_BINDINGS.iter().map(|binding| binding.name).collect();
+        assert_eq!(names.len(), DEFAULT_BINDINGS.len());
+        let actions: HashSet<Action> = DEFAULT_BINDINGS.iter().map(|binding| binding.action).collect();
+        assert_eq!(actions.len(), DEFAULT_BINDINGS.len());
+        // Keys only repeat between free-fly actions and ones gated off while flying
+        for (i, binding) in DEFAULT_BINDINGS.iter().enumerate() {
+            for other in &DEFAULT_BINDINGS[i + 1..] {
+                assert!(binding.key != other.key || !binding.clashes_with(other), "{} / {}", binding.name, other.name);
+            }
+        }
+    }
+
+    #[test]
+    fn every_default_key_round_trips_through_its_name() {
+        for binding in DEFAULT_BINDINGS {
+            // Letters, digits and F-keys are named by their label; the rest by `NAMED_KEYS`
+            let name = NAMED_KEYS
+                .iter()
+                .find(|(_, _, key)| *key == binding.key)
+                .map_or_else(|| key_label(binding.key).to_lowercase(), |(name, _, _)| name.to_string());
+            assert_eq!(parse_key(&name), Some(binding.key), "{} is bound to {:?}", binding.name, binding.key);
+        }
+        assert_eq!(parse_key("Q"), Some(KeyCode::KeyQ));
+        assert_eq!(parse_key("7"), Some(KeyCode::Digit7));
+        assert_eq!(parse_key("F11"), Some(KeyCode::F11));
+        assert_eq!(parse_key("pageup"), Some(KeyCode::PageUp));
+        assert_eq!(parse_key("f13"), None);
+        assert_eq!(parse_key("f0"), None);
+        assert_eq!(key_label(KeyCode::Space), "SPACE");
+        assert_eq!(key_label(KeyCode::BracketRight), "]");
+    }
+
+    #[test]
+    fn rebinding_moves_a_key_and_rejects_clashes() {
+        let mut bindings = KeyBindings::default();
+        bindings.rebind("spawn=enter").unwrap();
+        assert_eq!(bindings.key(Action::Spawn), KeyCode::Enter);
+        // Free-fly keys may only overlap actions that wait while flying
+        bindings.rebind("fly-up=v").unwrap();
+        assert!(bindings.rebind("fly-down=v").is_err());
+        assert!(bindings.rebind("fly-down=r").is_err());
+        bindings.rebind("glass=d").unwrap();
+        assert!(bindings.rebind("spawn-lights=d").is_err());
+        assert!(bindings.rebind("reset=l").is_err());
+        assert!(bindings.rebind("jump=space").is_err());
+        assert!(bindings.rebind("spawn").is_err());
+        assert!(bindings.rebind("spawn=hyper").is_err());
+    }
+}
@@ -0,0 +1,1096 @@
+// mesh.rs - Procedural meshes for the spawned shapes: icospheres, the other
+// platonic solids and torus knots, with flat or smooth shading and UVs.
+
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+// ---------------- CUSTOM MESH GENERATOR (ICOSAHEDRON) ----------------
+/// Above 6 subdivisions (81,920 triangles per shape) the test stops being useful.
+pub const MAX_SUBDIVISIONS: u8 = 6;
+
+fn icosphere_triangle_count(subdivisions: u8) -> u32 {
+    20 * 4u32.pow(subdivisions.min(MAX_SUBDIVISIONS) as u32)
+}
+
+/// Shared-vertex icosphere: positions on the sphere plus CCW triangle indices.
+/// Each subdivision splits every triangle into four and re-projects the new
+/// edge midpoints onto the sphere.
+fn icosphere_geometry(radius: f32, subdivisions: u8) -> (Vec<Vec3>, Vec<[u32; 3]>) {
+    let phi = (1.0 + 5.0f32.sqrt()) / 2.0;
+
+    let mut positions = vec![
+        Vec3::new(-1.0,  phi, 0.0).normalize(),
+        Vec3::new( 1.0,  phi, 0.0).normalize(),
+        Vec3::new(-1.0, -phi, 0.0).normalize(),
+        Vec3::new( 1.0, -phi, 0.0).normalize(),
+
+        Vec3::new( 0.0, -1.0,  phi).normalize(),
+        Vec3::new( 0.0,  1.0,  phi).normalize(),
+        Vec3::new( 0.0, -1.0, -phi).normalize(),
+        Vec3::new( 0.0,  1.0, -phi).normalize(),
+
+        Vec3::new( phi, 0.0, -1.0).normalize(),
+        Vec3::new( phi, 0.0,  1.0).normalize(),
+        Vec3::new(-phi, 0.0, -1.0).normalize(),
+        Vec3::new(-phi, 0.0,  1.0).normalize(),
+    ];
+
+    // Stand vertex 0 (and its opposite, vertex 3) on the poles so the UV
+    // singularities land on vertices instead of cutting through faces
+    let to_pole = Quat::from_rotation_arc(positions[0], Vec3::Y);
+    for position in &mut positions {
+        *position = to_pole * *position;
+    }
+
+    let mut faces = vec![
+        [0, 11, 5],  [0, 5, 1],   [0, 1, 7],   [0, 7, 10],  [0, 10, 11],
+        [1, 5, 9],   [5, 11, 4],  [11, 10, 2], [10, 7, 6],  [7, 1, 8],
+        [3, 9, 4],   [3, 4, 2],   [3, 2, 6],   [3, 6, 8],   [3, 8, 9],
+        [4, 9, 5],   [2, 4, 11],  [6, 2, 10],  [8, 6, 7],   [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions.min(MAX_SUBDIVISIONS) {
+        // Neighbouring triangles share edges, so each midpoint is created once
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::with_capacity(faces.len() * 3 / 2);
+        let mut midpoint = |a: u32, b: u32| -> u32 {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let mid = (positions[a as usize] + positions[b as usize]).normalize();
+                positions.push(mid);
+                positions.len() as u32 - 1
+            })
+        };
+
+        faces = faces
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let ab = midpoint(a, b);
+                let bc = midpoint(b, c);
+                let ca = midpoint(c, a);
+                [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    for position in positions.iter_mut() {
+        *position *= radius;
+    }
+
+    (positions, faces)
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub enum Shading {
+    /// Per-face normals; every triangle gets its own three vertices.
+    #[default]
+    Flat,
+    /// Shared vertices with normals pointing away from the center.
+    Smooth,
+}
+
+impl FromStr for Shading {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "flat" => Ok(Shading::Flat),
+            "smooth" => Ok(Shading::Smooth),
+            _ => Err(format!("Unknown shading: {} (expected flat or smooth)", value)),
+        }
+    }
+}
+
+/// Seeded displacement of icosphere vertices along their normals, for
+/// irregular shard-like shapes (`--shatter`).
+#[derive(Clone, Copy)]
+pub struct VertexNoise {
+    /// Maximum offset as a fraction of the radius; 0 leaves the mesh untouched.
+    pub amplitude: f32,
+    pub seed: u64,
+}
+
+impl VertexNoise {
+    pub const NONE: VertexNoise = VertexNoise { amplitude: 0.0, seed: 0 };
+
+    pub fn apply(self, positions: &mut [Vec3]) {
+        if self.amplitude == 0.0 {
+            return;
+        }
+
+        // Shared vertices move once, so faces stay connected
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        for position in positions.iter_mut() {
+            *position *= 1.0 + rng.gen_range(-self.amplitude..=self.amplitude);
+        }
+    }
+}
+
+/// Builds a radius-sized icosphere. With `tangents` set, MikkTSpace tangents
+/// are generated for normal mapping (see `add_tangents`).
+pub fn create_icosahedron_mesh(
+    radius: f32,
+    subdivisions: u8,
+    shading: Shading,
+    tangents: bool,
+    noise: VertexNoise,
+) -> Mesh {
+    let (mut positions, faces) = icosphere_geometry(radius, subdivisions);
+    noise.apply(&mut positions);
+    let mut mesh = shaded_mesh(positions, faces, shading);
+
+    if tangents {
+        add_tangents(&mut mesh);
+    }
+
+    mesh
+}
+
+pub fn stellated_triangle_count(subdivisions: u8) -> u32 {
+    3 * icosphere_triangle_count(subdivisions)
+}
+
+/// Icosphere with every face's centroid pushed out along the face normal by
+/// `spike` radii, turning the face into three flat-shaded triangles. A spike
+/// of 0 leaves them coplanar, i.e. the plain (flat) icosphere. Vertices lie
+/// within `radius * (1 + spike)`.
+pub fn create_stellated_mesh(radius: f32, subdivisions: u8, spike: f32, noise: VertexNoise) -> Mesh {
+    let (mut positions, faces) = icosphere_geometry(radius, subdivisions);
+    noise.apply(&mut positions);
+
+    let mut spiked = Vec::with_capacity(faces.len() * 3);
+    for [a, b, c] in faces {
+        let [p0, p1, p2] = [a, b, c].map(|i| positions[i as usize]);
+        let normal = (p1 - p0).cross(p2 - p0).normalize();
+        let apex = positions.len() as u32;
+        positions.push((p0 + p1 + p2) / 3.0 + normal * spike * radius);
+        spiked.extend([[a, b, apex], [b, c, apex], [c, a, apex]]);
+    }
+
+    flat_mesh(positions, spiked)
+}
+
+/// Generates tangents in place. A failure is logged and the mesh is left
+/// without them, so normal maps are ignored rather than the app panicking.
+pub fn add_tangents(mesh: &mut Mesh) {
+    if let Err(err) = mesh.generate_tangents() {
+        warn!("Skipping tangents for a shape mesh: {}", err);
+    }
+}
+
+fn shaded_mesh(positions: Vec<Vec3>, faces: Vec<[u32; 3]>, shading: Shading) -> Mesh {
+    match shading {
+        Shading::Flat => flat_mesh(positions, faces),
+        Shading::Smooth => smooth_mesh(positions, faces),
+    }
+}
+
+/// Every face gets its own 3 vertices (60 for the base icosahedron) with the
+/// face normal.
+fn flat_mesh(positions: Vec<Vec3>, faces: Vec<[u32; 3]>) -> Mesh {
+    let vertex_count = faces.len() * 3;
+    let mut final_positions = Vec::with_capacity(vertex_count);
+    let mut final_normals = Vec::with_capacity(vertex_count);
+    let mut final_uvs = Vec::with_capacity(vertex_count);
+    let mut final_indices = Vec::with_capacity(vertex_count);
+
+    for [idx0, idx1, idx2] in faces {
+        let p0 = positions[idx0 as usize];
+        let p1 = positions[idx1 as usize];
+        let p2 = positions[idx2 as usize];
+
+        let normal = (p1 - p0).cross(p2 - p0).normalize();
+
+        final_positions.extend([p0, p1, p2]);
+        final_normals.extend([normal; 3]);
+        final_uvs.extend(triangle_uvs([p0, p1, p2]));
+
+        let start_idx = final_indices.len() as u32;
+        final_indices.extend(start_idx..start_idx + 3);
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, final_positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, final_normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, final_uvs);
+    mesh.insert_indices(Indices::U32(final_indices));
+    mesh
+}
+
+/// Shared-vertex mesh with radial normals. Vertices whose UV differs between
+/// the triangles using them (seam and pole corners) are duplicated.
+fn smooth_mesh(mut positions: Vec<Vec3>, faces: Vec<[u32; 3]>) -> Mesh {
+    let mut uvs: Vec<Vec2> = positions.iter().map(|&p| sphere_uv(p)).collect();
+    let mut duplicates: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut indices = Vec::with_capacity(faces.len() * 3);
+
+    for face in faces {
+        let corners = face.map(|i| positions[i as usize]);
+
+        for (index, uv) in face.into_iter().zip(triangle_uvs(corners)) {
+            if uv == uvs[index as usize] {
+                indices.push(index);
+                continue;
+            }
+
+            let duplicate = *duplicates.entry((index, uv.x.to_bits())).or_insert_with(|| {
+                positions.push(positions[index as usize]);
+                uvs.push(uv);
+                positions.len() as u32 - 1
+            });
+            indices.push(duplicate);
+        }
+    }
+
+    let normals: Vec<Vec3> = positions.iter().map(|p| p.normalize()).collect();
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_indices(Indices::U32(indices));
+    mesh
+}
+
+/// Equirectangular UV of a point on the sphere; v = 0 at the north pole.
+fn sphere_uv(position: Vec3) -> Vec2 {
+    let n = position.normalize();
+    Vec2::new(
+        0.5 + n.z.atan2(n.x) / std::f32::consts::TAU,
+        n.y.clamp(-1.0, 1.0).acos() / std::f32::consts::PI,
+    )
+}
+
+/// UVs for one triangle with the seam and poles fixed up. A triangle wrapping
+/// across u = 1 is kept on the side holding most of it: corners on the other
+/// side are clamped to that edge of the texture (0.0 or 1.0), so every u stays
+/// in [0, 1] and no triangle spans more than half the texture. A corner
+/// sitting on a pole, where u is undefined, takes the mean u of the other two
+/// corners.
+fn triangle_uvs(corners: [Vec3; 3]) -> [Vec2; 3] {
+    let mut uvs = corners.map(sphere_uv);
+    let at_pole = corners.map(|p| {
+        let n = p.normalize();
+        n.x.abs() < 1e-6 && n.z.abs() < 1e-6
+    });
+
+    let (min_u, max_u) = (0..3)
+        .filter(|&i| !at_pole[i])
+        .fold((f32::MAX, f32::MIN), |(lo, hi), i| (lo.min(uvs[i].x), hi.max(uvs[i].x)));
+
+    if max_u - min_u > 0.5 {
+        // The centroid with the low corners unwrapped past 1 says which side wins
+        let (sum, count) = (0..3)
+            .filter(|&i| !at_pole[i])
+            .map(|i| if uvs[i].x < 0.5 { uvs[i].x + 1.0 } else { uvs[i].x })
+            .fold((0.0, 0), |(sum, count), u| (sum + u, count + 1));
+        let high_side = sum / count as f32 < 1.0;
+        for (uv, pole) in uvs.iter_mut().zip(at_pole) {
+            match (pole, high_side, uv.x < 0.5) {
+                (false, true, true) => uv.x = 1.0,
+                (false, false, false) => uv.x = 0.0,
+                _ => {}
+            }
+        }
+    }
+
+    let (sum, count) = (0..3)
+        .filter(|&i| !at_pole[i])
+        .fold((0.0, 0), |(sum, count), i| (sum + uvs[i].x, count + 1));
+    for (uv, pole) in uvs.iter_mut().zip(at_pole) {
+        if pole && count > 0 {
+            uv.x = sum / count as f32;
+        }
+    }
+
+    uvs
+}
+
+// ---------------- CUSTOM MESH GENERATOR (PLATONIC SOLIDS) ----------------
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub enum PlatonicSolid {
+    Tetrahedron,
+    Octahedron,
+    #[default]
+    Icosahedron,
+    /// Twelve pentagons, each fanned into five triangles around its center.
+    Dodecahedron,
+}
+
+impl PlatonicSolid {
+    pub const ALL: [PlatonicSolid; 4] = [
+        PlatonicSolid::Tetrahedron,
+        PlatonicSolid::Octahedron,
+        PlatonicSolid::Icosahedron,
+        PlatonicSolid::Dodecahedron,
+    ];
+
+    /// Subdivision only applies to the icosahedron.
+    pub fn triangle_count(self, subdivisions: u8) -> u32 {
+        match self {
+            PlatonicSolid::Tetrahedron => 4,
+            PlatonicSolid::Octahedron => 8,
+            PlatonicSolid::Icosahedron => icosphere_triangle_count(subdivisions),
+            PlatonicSolid::Dodecahedron => 60,
+        }
+    }
+}
+
+impl FromStr for PlatonicSolid {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "tetrahedron" => Ok(PlatonicSolid::Tetrahedron),
+            "octahedron" => Ok(PlatonicSolid::Octahedron),
+            "icosahedron" => Ok(PlatonicSolid::Icosahedron),
+            "dodecahedron" => Ok(PlatonicSolid::Dodecahedron),
+            _ => Err(format!(
+                "Unknown shape: {} (expected tetrahedron, octahedron, icosahedron, dodecahedron, stellated, dome, mixed or torus-knot)",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PlatonicSolid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlatonicSolid::Tetrahedron => write!(f, "tetrahedron"),
+            PlatonicSolid::Octahedron => write!(f, "octahedron"),
+            PlatonicSolid::Icosahedron => write!(f, "icosahedron"),
+            PlatonicSolid::Dodecahedron => write!(f, "dodecahedron"),
+        }
+    }
+}
+
+/// Corner positions (circumradius `radius`) and CCW triangles of a solid.
+fn platonic_geometry(kind: PlatonicSolid, radius: f32) -> (Vec<Vec3>, Vec<[u32; 3]>) {
+    let (mut positions, faces) = match kind {
+        PlatonicSolid::Tetrahedron => {
+            // Apex on the north pole, base ring below it
+            let ring = (8.0f32 / 9.0).sqrt();
+            let mut positions = vec![Vec3::Y];
+            positions.extend((0..3).map(|i| {
+                let angle = i as f32 * std::f32::consts::TAU / 3.0;
+                Vec3::new(ring * angle.cos(), -1.0 / 3.0, ring * angle.sin())
+            }));
+            (positions, vec![[1, 2, 3], [0, 2, 1], [0, 3, 2], [0, 1, 3]])
+        }
+        PlatonicSolid::Octahedron => (
+            vec![Vec3::X, Vec3::NEG_X, Vec3::Y, Vec3::NEG_Y, Vec3::Z, Vec3::NEG_Z],
+            vec![
+                [2, 4, 0], [2, 0, 5], [2, 5, 1], [2, 1, 4],
+                [3, 0, 4], [3, 5, 0], [3, 1, 5], [3, 4, 1],
+            ],
+        ),
+        PlatonicSolid::Icosahedron => icosphere_geometry(1.0, 0),
+        PlatonicSolid::Dodecahedron => dodecahedron_geometry(),
+    };
+
+    for position in positions.iter_mut() {
+        *position *= radius;
+    }
+
+    (positions, faces)
+}
+
+/// The dual of the icosahedron: a corner at every icosahedron face and a
+/// pentagon around every icosahedron vertex.
+fn dodecahedron_geometry() -> (Vec<Vec3>, Vec<[u32; 3]>) {
+    let (ico_positions, ico_faces) = icosphere_geometry(1.0, 0);
+
+    let mut positions: Vec<Vec3> = ico_faces
+        .iter()
+        .map(|&[a, b, c]| (ico_positions[a as usize] + ico_positions[b as usize] + ico_positions[c as usize]).normalize())
+        .collect();
+    let mut faces = Vec::with_capacity(60);
+
+    for (vertex, &axis) in ico_positions.iter().enumerate() {
+        let mut ring: Vec<u32> = (0..ico_faces.len() as u32)
+            .filter(|&face| ico_faces[face as usize].contains(&(vertex as u32)))
+            .collect();
+
+        // (tangent, bitangent, axis) is right-handed, so increasing angle is
+        // counter-clockwise seen from outside
+        let tangent = axis.any_orthonormal_vector();
+        let bitangent = axis.cross(tangent);
+        let angle = |corner: u32| {
+            let p = positions[corner as usize];
+            p.dot(bitangent).atan2(p.dot(tangent))
+        };
+        ring.sort_by(|&a, &b| angle(a).total_cmp(&angle(b)));
+
+        // Fan from the pentagon's center so a pole lands on a vertex
+        let center = ring.iter().map(|&corner| positions[corner as usize]).sum::<Vec3>() / ring.len() as f32;
+        positions.push(center);
+        let center = positions.len() as u32 - 1;
+
+        for i in 0..ring.len() {
+            faces.push([center, ring[i], ring[(i + 1) % ring.len()]]);
+        }
+    }
+
+    (positions, faces)
+}
+
+pub fn create_platonic_mesh(kind: PlatonicSolid, radius: f32, shading: Shading) -> Mesh {
+    let (positions, faces) = platonic_geometry(kind, radius);
+    shaded_mesh(positions, faces, shading)
+}
+
+// ---------------- CUSTOM MESH GENERATOR (DOME) ----------------
+/// Upper half of an icosphere clipped at y = 0: compacted positions, CCW
+/// surface triangles and the directed boundary edges along the equator.
+/// Clipped edges get one shared vertex per edge, so neighbouring triangles
+/// stay welded and the equator has no T-junctions.
+fn dome_geometry(radius: f32, subdivisions: u8) -> (Vec<Vec3>, Vec<[u32; 3]>, Vec<[u32; 2]>) {
+    let (mut positions, faces) = icosphere_geometry(radius, subdivisions);
+    // Vertices that already sit on the equator are kept rather than split
+    for position in &mut positions {
+        if position.y.abs() < radius * 1e-5 {
+            position.y = 0.0;
+        }
+    }
+
+    let mut crossings: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut surface = Vec::new();
+    for face in faces {
+        // Sutherland-Hodgman against y >= 0, then fan the kept polygon
+        let mut polygon = Vec::with_capacity(4);
+        for k in 0..3 {
+            let (a, b) = (face[k], face[(k + 1) % 3]);
+            let (pa, pb) = (positions[a as usize], positions[b as usize]);
+            if pa.y >= 0.0 {
+                polygon.push(a);
+            }
+            if (pa.y > 0.0 && pb.y < 0.0) || (pa.y < 0.0 && pb.y > 0.0) {
+                let crossing = *crossings.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    let point = pa.lerp(pb, pa.y / (pa.y - pb.y));
+                    // Back out onto the sphere without leaving the plane
+                    positions.push(Vec3::new(point.x, 0.0, point.z).normalize() * radius);
+                    positions.len() as u32 - 1
+                });
+                polygon.push(crossing);
+            }
+        }
+        for k in 1..polygon.len().saturating_sub(1) {
+            surface.push([polygon[0], polygon[k], polygon[k + 1]]);
+        }
+    }
+
+    // Drop the vertices of the discarded lower half
+    let mut remap = vec![u32::MAX; positions.len()];
+    let mut kept = Vec::new();
+    for index in surface.iter_mut().flatten() {
+        if remap[*index as usize] == u32::MAX {
+            remap[*index as usize] = kept.len() as u32;
+            kept.push(positions[*index as usize]);
+        }
+        *index = remap[*index as usize];
+    }
+
+    let edges: HashSet<(u32, u32)> = surface
+        .iter()
+        .flat_map(|&[a, b, c]| [(a, b), (b, c), (c, a)])
+        .collect();
+    let boundary = edges
+        .iter()
+        .filter(|&&(a, b)| !edges.contains(&(b, a)))
+        .map(|&(a, b)| [a, b])
+        .collect();
+
+    (kept, surface, boundary)
+}
+
+pub fn dome_triangle_count(subdivisions: u8) -> u32 {
+    let (_, surface, boundary) = dome_geometry(1.0, subdivisions);
+    (surface.len() + boundary.len()) as u32
+}
+
+/// Hemisphere standing on y = 0 for shapes that sit on the floor, closed by
+/// a flat cap fanned from the center (normal -Y, planar UVs).
+pub fn create_dome_mesh(radius: f32, subdivisions: u8, shading: Shading) -> Mesh {
+    let (positions, surface, boundary) = dome_geometry(radius, subdivisions);
+
+    let mut cap_positions = vec![Vec3::ZERO];
+    let mut cap_indices = Vec::with_capacity(boundary.len() * 3);
+    let mut cap_vertices: HashMap<u32, u32> = HashMap::new();
+    for [a, b] in boundary {
+        // Walk each edge backwards so the cap faces down
+        let [a, b] = [a, b].map(|index| {
+            *cap_vertices.entry(index).or_insert_with(|| {
+                cap_positions.push(positions[index as usize]);
+                cap_positions.len() as u32 - 1
+            })
+        });
+        cap_indices.extend([b, a, 0]);
+    }
+    let cap_uvs: Vec<Vec2> = cap_positions
+        .iter()
+        .map(|p| Vec2::new(0.5 + p.x / (2.0 * radius), 0.5 + p.z / (2.0 * radius)))
+        .collect();
+
+    let mut cap = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
+    cap.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![Vec3::NEG_Y; cap_positions.len()]);
+    cap.insert_attribute(Mesh::ATTRIBUTE_POSITION, cap_positions);
+    cap.insert_attribute(Mesh::ATTRIBUTE_UV_0, cap_uvs);
+    cap.insert_indices(Indices::U32(cap_indices));
+
+    let mut mesh = shaded_mesh(positions, surface, shading);
+    mesh.merge(&cap);
+    mesh
+}
+
+// ---------------- CUSTOM MESH GENERATOR (TORUS KNOT) ----------------
+/// Parameters of the `--shape torus-knot` mesh.
+#[derive(Clone, Copy)]
+pub struct TorusKnot {
+    /// Windings around the torus axis.
+    pub p: u32,
+    /// Windings through the torus hole.
+    pub q: u32,
+    pub tube_radius: f32,
+    /// Rings along the curve.
+    pub segments: u32,
+    /// Vertices around each ring.
+    pub sides: u32,
+}
+
+impl Default for TorusKnot {
+    fn default() -> Self {
+        // Trefoil, 16,384 triangles
+        Self { p: 2, q: 3, tube_radius: 0.4, segments: 256, sides: 32 }
+    }
+}
+
+impl TorusKnot {
+    pub fn triangle_count(self) -> u32 {
+        self.segments * self.sides * 2
+    }
+
+    pub fn validate(self) -> Result<(), String> {
+        validate_torus_knot(self.p, self.q, self.tube_radius, self.segments, self.sides)
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn validate_torus_knot(p: u32, q: u32, tube_radius: f32, segments: u32, sides: u32) -> Result<(), String> {
+    if p == 0 || q == 0 {
+        return Err(format!("Torus knot ({}, {}): p and q must be at least 1", p, q));
+    }
+    // With a common factor the curve closes early and traces a link instead
+    if gcd(p, q) != 1 {
+        return Err(format!("Torus knot ({}, {}): p and q must be coprime", p, q));
+    }
+    if !(tube_radius > 0.0 && tube_radius.is_finite()) {
+        return Err(format!("Torus knot tube radius must be positive, got {}", tube_radius));
+    }
+    let min_segments = 8 * p.max(q);
+    if segments < min_segments {
+        return Err(format!(
+            "Torus knot ({}, {}) needs at least {} segments, got {}",
+            p, q, min_segments, segments
+        ));
+    }
+    if sides < 3 {
+        return Err(format!("Torus knot needs at least 3 sides, got {}", sides));
+    }
+    Ok(())
+}
+
+/// Point on the (p, q) torus knot; the centre line stays within radius 3.
+fn torus_knot_point(p: u32, q: u32, t: f32) -> Vec3 {
+    let r = 2.0 + (q as f32 * t).cos();
+    Vec3::new(r * (p as f32 * t).cos(), -(q as f32 * t).sin(), r * (p as f32 * t).sin())
+}
+
+/// A tube swept along the (p, q) torus knot with smooth normals and UVs (u
+/// along the curve, v around the tube). Seam rings are duplicated, giving
+/// `(segments + 1) * (sides + 1)` vertices and `2 * segments * sides`
+/// triangles.
+pub fn create_torus_knot_mesh(p: u32, q: u32, tube_radius: f32, segments: u32, sides: u32) -> Result<Mesh, String> {
+    validate_torus_knot(p, q, tube_radius, segments, sides)?;
+
+    let vertex_count = ((segments + 1) * (sides + 1)) as usize;
+    let mut positions = Vec::with_capacity(vertex_count);
+    let mut normals = Vec::with_capacity(vertex_count);
+    let mut uvs = Vec::with_capacity(vertex_count);
+
+    for segment in 0..=segments {
+        let u = segment as f32 / segments as f32;
+        let t = u * std::f32::consts::TAU;
+        let center = torus_knot_point(p, q, t);
+        let ahead = torus_knot_point(p, q, t + 0.01);
+
+        // The curve winds around the origin, so center + ahead is never
+        // parallel to the tangent and gives a stable frame
+        let tangent = (ahead - center).normalize();
+        let binormal = tangent.cross(ahead + center).normalize();
+        let normal = binormal.cross(tangent);
+
+        for side in 0..=sides {
+            let v = side as f32 / sides as f32;
+            let (sin, cos) = (v * std::f32::consts::TAU).sin_cos();
+            let direction = normal * cos + binormal * sin;
+
+            positions.push(center + direction * tube_radius);
+            normals.push(direction);
+            uvs.push(Vec2::new(u, v));
+        }
+    }
+
+    let ring = sides + 1;
+    let mut indices = Vec::with_capacity((segments * sides * 6) as usize);
+    for segment in 0..segments {
+        for side in 0..sides {
+            let a = segment * ring + side;
+            let b = a + ring;
+            indices.extend([a, a + 1, b, a + 1, b + 1, b]);
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_indices(Indices::U32(indices));
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::render::mesh::VertexAttributeValues;
+
+    fn flat_icosahedron(radius: f32) -> Mesh {
+        create_icosahedron_mesh(radius, 0, Shading::Flat, false, VertexNoise::NONE)
+    }
+
+    #[test]
+    fn flat_icosahedron_has_60_vertices() {
+        let mesh = flat_icosahedron(1.0);
+
+        assert_eq!(mesh.attribute(Mesh::ATTRIBUTE_POSITION).unwrap().len(), 60);
+        assert_eq!(mesh.attribute(Mesh::ATTRIBUTE_NORMAL).unwrap().len(), 60);
+        assert_eq!(mesh.indices().unwrap().len(), 60);
+    }
+
+    #[test]
+    fn flat_icosahedron_attributes_and_index_format() {
+        let mesh = flat_icosahedron(1.0);
+
+        assert_eq!(mesh.primitive_topology(), PrimitiveTopology::TriangleList);
+        assert!(matches!(
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION),
+            Some(VertexAttributeValues::Float32x3(_))
+        ));
+        assert!(matches!(
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL),
+            Some(VertexAttributeValues::Float32x3(_))
+        ));
+        assert!(matches!(mesh.indices(), Some(Indices::U32(_))));
+    }
+
+    #[test]
+    fn flat_icosahedron_positions_on_radius_and_unit_normals() {
+        for radius in [0.25, 0.5, 1.5] {
+            let mesh = flat_icosahedron(radius);
+            let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION).and_then(|a| a.as_float3()).unwrap();
+            let normals = mesh.attribute(Mesh::ATTRIBUTE_NORMAL).and_then(|a| a.as_float3()).unwrap();
+
+            for position in positions {
+                assert!((Vec3::from_array(*position).length() - radius).abs() < 1e-5);
+            }
+            for normal in normals {
+                assert!((Vec3::from_array(*normal).length() - 1.0).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn flat_icosahedron_winding_is_outward() {
+        let mesh = flat_icosahedron(1.0);
+        let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION).and_then(|a| a.as_float3()).unwrap();
+        let normals = mesh.attribute(Mesh::ATTRIBUTE_NORMAL).and_then(|a| a.as_float3()).unwrap();
+        let indices: Vec<usize> = mesh.indices().unwrap().iter().collect();
+
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| Vec3::from_array(positions[triangle[i]]));
+            let geometric = (b - a).cross(c - a).normalize();
+            let centroid = (a + b + c) / 3.0;
+
+            assert!(geometric.dot(centroid) > 0.0, "face wound inward");
+            for &i in triangle {
+                assert!(Vec3::from_array(normals[i]).dot(geometric) > 1.0 - 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn subdivision_face_counts() {
+        for subdivisions in 0..=4 {
+            let (_, faces) = icosphere_geometry(1.0, subdivisions);
+            assert_eq!(faces.len() as u32, icosphere_triangle_count(subdivisions));
+            assert_eq!(faces.len(), 20 * 4usize.pow(subdivisions as u32));
+
+            let mesh = create_icosahedron_mesh(1.0, subdivisions, Shading::Flat, false, VertexNoise::NONE);
+            assert_eq!(mesh.count_vertices(), faces.len() * 3);
+        }
+    }
+
+    #[test]
+    fn midpoints_are_shared_between_faces() {
+        // Euler: V = E - F + 2, with E = 3F / 2 for a closed triangle mesh
+        for subdivisions in 0..=4 {
+            let (positions, faces) = icosphere_geometry(1.0, subdivisions);
+            assert_eq!(positions.len(), faces.len() * 3 / 2 - faces.len() + 2);
+        }
+    }
+
+    #[test]
+    fn subdivided_vertices_lie_on_sphere() {
+        let radius = 2.5;
+        for subdivisions in 0..=4 {
+            let (positions, _) = icosphere_geometry(radius, subdivisions);
+            for position in positions {
+                assert!((position.length() - radius).abs() < 1e-4, "{:?} is off the sphere", position);
+            }
+        }
+    }
+
+    fn mesh_uvs(mesh: &Mesh) -> Vec<Vec2> {
+        match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float32x2(uvs)) => uvs.iter().map(|&uv| Vec2::from_array(uv)).collect(),
+            _ => panic!("mesh has no UV_0 attribute"),
+        }
+    }
+
+    #[test]
+    fn smooth_shading_shares_vertices() {
+        let mesh = create_icosahedron_mesh(1.0, 0, Shading::Smooth, false, VertexNoise::NONE);
+
+        let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION).and_then(|a| a.as_float3()).unwrap();
+        let normals = mesh.attribute(Mesh::ATTRIBUTE_NORMAL).and_then(|a| a.as_float3()).unwrap();
+        assert_eq!(normals.len(), positions.len());
+        for normal in normals {
+            assert!((Vec3::from_array(*normal).length() - 1.0).abs() < 1e-5);
+        }
+
+        // 12 distinct corners; anything beyond that is a UV seam duplicate
+        let mut distinct: Vec<[f32; 3]> = positions.to_vec();
+        distinct.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        distinct.dedup();
+        assert_eq!(distinct.len(), 12);
+        assert!(positions.len() < 60);
+
+        let flat = create_icosahedron_mesh(1.0, 0, Shading::Flat, false, VertexNoise::NONE);
+        assert_eq!(mesh.indices().unwrap().len(), 60);
+        assert_eq!(mesh.indices().unwrap().len(), flat.indices().unwrap().len());
+    }
+
+    #[test]
+    fn uvs_are_in_range_and_seam_fixed() {
+        for shading in [Shading::Flat, Shading::Smooth] {
+            for subdivisions in 0..=3 {
+                let mesh = create_icosahedron_mesh(1.0, subdivisions, shading, false, VertexNoise::NONE);
+                let uvs = mesh_uvs(&mesh);
+                assert_eq!(uvs.len(), mesh.count_vertices());
+
+                for uv in &uvs {
+                    assert!((0.0..=1.0).contains(&uv.y), "v out of range: {:?}", uv);
+                    assert!((0.0..=1.0).contains(&uv.x), "u out of range: {:?}", uv);
+                }
+
+                let indices: Vec<usize> = mesh.indices().unwrap().iter().collect();
+                for triangle in indices.chunks_exact(3) {
+                    let u = triangle.iter().map(|&i| uvs[i].x);
+                    let span = u.clone().fold(f32::MIN, f32::max) - u.fold(f32::MAX, f32::min);
+                    assert!(span <= 0.5, "triangle spans {} in u", span);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tangents_are_generated_for_both_shadings() {
+        for shading in [Shading::Flat, Shading::Smooth] {
+            for subdivisions in 0..=2 {
+                let mesh = create_icosahedron_mesh(1.0, subdivisions, shading, true, VertexNoise::NONE);
+                let normals = mesh.attribute(Mesh::ATTRIBUTE_NORMAL).and_then(|a| a.as_float3()).unwrap();
+                let tangents = match mesh.attribute(Mesh::ATTRIBUTE_TANGENT) {
+                    Some(VertexAttributeValues::Float32x4(tangents)) => tangents,
+                    _ => panic!("no tangents for {:?} shading, subdivisions {}", shading, subdivisions),
+                };
+                assert_eq!(tangents.len(), normals.len());
+
+                for (tangent, normal) in tangents.iter().zip(normals) {
+                    let direction = Vec3::new(tangent[0], tangent[1], tangent[2]);
+                    assert!((direction.length() - 1.0).abs() < 1e-3);
+                    assert!(direction.dot(Vec3::from_array(*normal)).abs() < 1e-3);
+                    assert_eq!(tangent[3].abs(), 1.0);
+                }
+            }
+        }
+
+        assert!(create_icosahedron_mesh(1.0, 0, Shading::Flat, false, VertexNoise::NONE)
+            .attribute(Mesh::ATTRIBUTE_TANGENT)
+            .is_none());
+    }
+
+    #[test]
+    fn platonic_faces_point_outward() {
+        for kind in PlatonicSolid::ALL {
+            let mesh = create_platonic_mesh(kind, 1.0, Shading::Flat);
+            let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION).and_then(|a| a.as_float3()).unwrap();
+            let normals = mesh.attribute(Mesh::ATTRIBUTE_NORMAL).and_then(|a| a.as_float3()).unwrap();
+            assert_eq!(positions.len() as u32, kind.triangle_count(0) * 3, "{}", kind);
+
+            for (corners, normal) in positions.chunks_exact(3).zip(normals.chunks_exact(3)) {
+                let centroid = corners.iter().map(|&p| Vec3::from_array(p)).sum::<Vec3>() / 3.0;
+                let normal = Vec3::from_array(normal[0]);
+                assert!(normal.dot(centroid.normalize()) > 0.0, "{} has an inward face", kind);
+            }
+        }
+    }
+
+    #[test]
+    fn stellated_spikes_are_flat_and_outward() {
+        let mesh = create_stellated_mesh(1.0, 1, 0.5, VertexNoise::NONE);
+        let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION).and_then(|a| a.as_float3()).unwrap();
+        let normals = mesh.attribute(Mesh::ATTRIBUTE_NORMAL).and_then(|a| a.as_float3()).unwrap();
+        assert_eq!(positions.len() as u32, stellated_triangle_count(1) * 3);
+
+        for (corners, normal) in positions.chunks_exact(3).zip(normals.chunks_exact(3)) {
+            let [p0, p1, p2] = [0, 1, 2].map(|i| Vec3::from_array(corners[i]));
+            let face_normal = (p1 - p0).cross(p2 - p0).normalize();
+            let centroid = (p0 + p1 + p2) / 3.0;
+            assert!(normal.iter().all(|&n| Vec3::from_array(n).abs_diff_eq(face_normal, 1e-5)));
+            assert!(face_normal.dot(centroid) > 0.0);
+            assert!(corners.iter().all(|&p| Vec3::from_array(p).length() <= 1.5 + 1e-5));
+        }
+    }
+
+    #[test]
+    fn zero_spike_reproduces_the_icosphere() {
+        let base = create_icosahedron_mesh(1.0, 1, Shading::Flat, false, VertexNoise::NONE);
+        let stellated = create_stellated_mesh(1.0, 1, 0.0, VertexNoise::NONE);
+        let base_positions = base.attribute(Mesh::ATTRIBUTE_POSITION).and_then(|a| a.as_float3()).unwrap();
+        let base_normals = base.attribute(Mesh::ATTRIBUTE_NORMAL).and_then(|a| a.as_float3()).unwrap();
+        let positions = stellated.attribute(Mesh::ATTRIBUTE_POSITION).and_then(|a| a.as_float3()).unwrap();
+        let normals = stellated.attribute(Mesh::ATTRIBUTE_NORMAL).and_then(|a| a.as_float3()).unwrap();
+
+        let area = |corners: &[[f32; 3]]| {
+            let [p0, p1, p2] = [0, 1, 2].map(|i| Vec3::from_array(corners[i]));
+            (p1 - p0).cross(p2 - p0).length() / 2.0
+        };
+        // Base face i became stellated triangles 3i..3i+3: same normal, same total area
+        for (face, (corners, normal)) in base_positions.chunks_exact(3).zip(base_normals.chunks_exact(3)).enumerate() {
+            let parts = &positions[face * 9..face * 9 + 9];
+            assert!((parts.chunks_exact(3).map(area).sum::<f32>() - area(corners)).abs() < 1e-5);
+            for part_normal in &normals[face * 9..face * 9 + 9] {
+                assert!(Vec3::from_array(*part_normal).abs_diff_eq(Vec3::from_array(normal[0]), 1e-5));
+            }
+        }
+    }
+
+    #[test]
+    fn dome_is_closed_and_stands_on_the_equator() {
+        for (subdivisions, shading) in [(0, Shading::Flat), (2, Shading::Smooth), (3, Shading::Flat)] {
+            let mesh = create_dome_mesh(1.0, subdivisions, shading);
+            let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION).and_then(|a| a.as_float3()).unwrap();
+            let normals = mesh.attribute(Mesh::ATTRIBUTE_NORMAL).and_then(|a| a.as_float3()).unwrap();
+            let indices: Vec<usize> = mesh.indices().unwrap().iter().collect();
+            assert_eq!(indices.len() as u32, dome_triangle_count(subdivisions) * 3);
+            assert!(positions.iter().all(|p| p[1] >= 0.0 && Vec3::from_array(*p).length() <= 1.0 + 1e-5));
+
+            // Weld by position: every edge is shared by exactly two triangles
+            // with opposite directions, so the equator and the cap are closed
+            let key = |i: usize| positions[i].map(f32::to_bits);
+            let mut edges: HashMap<_, u32> = HashMap::new();
+            for triangle in indices.chunks_exact(3) {
+                for k in 0..3 {
+                    *edges.entry((key(triangle[k]), key(triangle[(k + 1) % 3]))).or_default() += 1;
+                }
+            }
+            for (&(a, b), &count) in &edges {
+                assert_eq!(count, 1, "edge used twice in one direction at subdivisions {}", subdivisions);
+                assert_eq!(edges.get(&(b, a)), Some(&1), "open edge at subdivisions {}", subdivisions);
+            }
+
+            // Near-equator vertices are exactly on it; the cap faces down
+            for triangle in indices.chunks_exact(3) {
+                let corners = triangle.iter().map(|&i| Vec3::from_array(positions[i])).collect::<Vec<_>>();
+                if corners.iter().all(|p| p.y == 0.0) {
+                    assert!(triangle.iter().all(|&i| normals[i] == [0.0, -1.0, 0.0]));
+                }
+            }
+            assert!(positions.iter().all(|p| p[1] == 0.0 || p[1] > 1e-4));
+        }
+    }
+
+    #[test]
+    fn torus_knot_is_validated_and_faces_outward() {
+        assert!(create_torus_knot_mesh(2, 4, 0.4, 256, 32).is_err());
+        assert!(create_torus_knot_mesh(0, 3, 0.4, 256, 32).is_err());
+        assert!(create_torus_knot_mesh(2, 3, 0.4, 16, 32).is_err());
+        assert!(create_torus_knot_mesh(2, 3, 0.4, 256, 2).is_err());
+        assert!(create_torus_knot_mesh(2, 3, 0.0, 256, 32).is_err());
+
+        let knot = TorusKnot::default();
+        let mesh = create_torus_knot_mesh(knot.p, knot.q, knot.tube_radius, knot.segments, knot.sides).unwrap();
+        let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION).and_then(|a| a.as_float3()).unwrap();
+        let normals = mesh.attribute(Mesh::ATTRIBUTE_NORMAL).and_then(|a| a.as_float3()).unwrap();
+        let indices: Vec<usize> = mesh.indices().unwrap().iter().collect();
+        assert_eq!(positions.len() as u32, (knot.segments + 1) * (knot.sides + 1));
+        assert_eq!(indices.len() as u32, knot.triangle_count() * 3);
+
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| Vec3::from_array(positions[triangle[i]]));
+            let face_normal = (b - a).cross(c - a);
+            assert!(face_normal.dot(Vec3::from_array(normals[triangle[0]])) > 0.0);
+        }
+    }
+
+
+    /// The unit icosahedron from before `--shatter`, pole-aligned: a golden
+    /// copy, so changes to the default mesh show up here.
+    const BASELINE_CORNERS: [[f32; 3]; 12] = [
+        [0.0, 1.0, 0.0],
+        [0.8944272, 0.4472136, 0.0],
+        [-0.8944272, -0.4472136, 0.0],
+        [0.0, -1.0, 0.0],
+        [-0.2763932, -0.4472136, 0.8506508],
+        [0.2763932, 0.4472136, 0.8506508],
+        [-0.2763932, -0.4472136, -0.8506508],
+        [0.2763932, 0.4472136, -0.8506508],
+        [0.7236068, -0.4472136, -0.5257311],
+        [0.7236068, -0.4472136, 0.5257311],
+        [-0.7236068, 0.4472136, -0.5257311],
+        [-0.7236068, 0.4472136, 0.5257311],
+    ];
+
+    const BASELINE_FACES: [[usize; 3]; 20] = [
+        [0, 11, 5],  [0, 5, 1],   [0, 1, 7],   [0, 7, 10],  [0, 10, 11],
+        [1, 5, 9],   [5, 11, 4],  [11, 10, 2], [10, 7, 6],  [7, 1, 8],
+        [3, 9, 4],   [3, 4, 2],   [3, 2, 6],   [3, 6, 8],   [3, 8, 9],
+        [4, 9, 5],   [2, 4, 11],  [6, 2, 10],  [8, 6, 7],   [9, 8, 1],
+    ];
+
+    /// Face normals of `BASELINE_FACES`, in order.
+    const BASELINE_NORMALS: [[f32; 3]; 20] = [
+        [-0.1875925, 0.7946545, 0.5773503],
+        [0.4911235, 0.7946545, 0.3568221],
+        [0.4911235, 0.7946545, -0.3568221],
+        [-0.1875925, 0.7946545, -0.5773503],
+        [-0.6070620, 0.7946545, 0.0],
+        [0.7946545, 0.1875925, 0.5773503],
+        [-0.3035310, 0.1875925, 0.9341724],
+        [-0.9822469, 0.1875925, 0.0],
+        [-0.3035310, 0.1875925, -0.9341724],
+        [0.7946545, 0.1875925, -0.5773503],
+        [0.1875925, -0.7946545, 0.5773503],
+        [-0.4911235, -0.7946545, 0.3568221],
+        [-0.4911235, -0.7946545, -0.3568221],
+        [0.1875925, -0.7946545, -0.5773503],
+        [0.6070620, -0.7946545, 0.0],
+        [0.3035310, -0.1875925, 0.9341724],
+        [-0.7946545, -0.1875925, 0.5773503],
+        [-0.7946545, -0.1875925, -0.5773503],
+        [0.3035310, -0.1875925, -0.9341724],
+        [0.9822469, -0.1875925, 0.0],
+    ];
+
+    fn assert_close(actual: [f32; 3], expected: [f32; 3]) {
+        assert!(
+            Vec3::from_array(actual).abs_diff_eq(Vec3::from_array(expected), 1e-6),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn zero_noise_matches_the_baseline_icosahedron() {
+        let noiseless = |shading| {
+            create_icosahedron_mesh(1.0, 0, shading, false, VertexNoise { amplitude: 0.0, seed: 1234 })
+        };
+
+        let flat = noiseless(Shading::Flat);
+        let positions = flat.attribute(Mesh::ATTRIBUTE_POSITION).and_then(|a| a.as_float3()).unwrap();
+        let normals = flat.attribute(Mesh::ATTRIBUTE_NORMAL).and_then(|a| a.as_float3()).unwrap();
+        assert_eq!(positions.len(), 60);
+        for (face, (corners, expected_normal)) in BASELINE_FACES.iter().zip(BASELINE_NORMALS).enumerate() {
+            for (corner, &index) in corners.iter().enumerate() {
+                assert_close(positions[face * 3 + corner], BASELINE_CORNERS[index]);
+                assert_close(normals[face * 3 + corner], expected_normal);
+            }
+        }
+        assert!(flat.indices().unwrap().iter().eq(0..60));
+
+        // Smooth shading shares the corners (plus seam duplicates) and points
+        // each normal away from the center
+        let smooth = noiseless(Shading::Smooth);
+        let positions = smooth.attribute(Mesh::ATTRIBUTE_POSITION).and_then(|a| a.as_float3()).unwrap();
+        let normals = smooth.attribute(Mesh::ATTRIBUTE_NORMAL).and_then(|a| a.as_float3()).unwrap();
+        let corner_of = |position: [f32; 3]| {
+            BASELINE_CORNERS
+                .iter()
+                .position(|&corner| Vec3::from_array(position).abs_diff_eq(Vec3::from_array(corner), 1e-6))
+                .unwrap_or_else(|| panic!("{:?} is not a baseline corner", position))
+        };
+        for (&position, &normal) in positions.iter().zip(normals) {
+            assert_close(normal, BASELINE_CORNERS[corner_of(position)]);
+        }
+        let indices: Vec<usize> = smooth.indices().unwrap().iter().collect();
+        for (triangle, corners) in indices.chunks_exact(3).zip(BASELINE_FACES) {
+            assert_eq!(triangle.iter().map(|&i| corner_of(positions[i])).collect::<Vec<_>>(), corners);
+        }
+
+        // With no amplitude the seed changes nothing, at any subdivision
+        for shading in [Shading::Flat, Shading::Smooth] {
+            for subdivisions in 0..=2 {
+                let seeded = create_icosahedron_mesh(1.0, subdivisions, shading, false, VertexNoise { amplitude: 0.0, seed: 1234 });
+                let unseeded = create_icosahedron_mesh(1.0, subdivisions, shading, false, VertexNoise::NONE);
+                for attribute in [Mesh::ATTRIBUTE_POSITION, Mesh::ATTRIBUTE_NORMAL, Mesh::ATTRIBUTE_UV_0] {
+                    assert_eq!(
+                        seeded.attribute(attribute).unwrap().get_bytes(),
+                        unseeded.attribute(attribute).unwrap().get_bytes()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn noise_displaces_radially_and_is_seeded() {
+        let noise = VertexNoise { amplitude: 0.2, seed: 7 };
+        let (mut positions, _) = icosphere_geometry(1.0, 1);
+        let original = positions.clone();
+        noise.apply(&mut positions);
+
+        let (mut again, _) = icosphere_geometry(1.0, 1);
+        noise.apply(&mut again);
+        assert_eq!(positions, again);
+
+        for (moved, start) in positions.iter().zip(&original) {
+            assert!(moved.normalize().distance(*start) < 1e-5);
+            assert!((moved.length() - 1.0).abs() <= 0.2 + 1e-5);
+        }
+        assert_ne!(positions, original);
+    }
+
+    #[test]
+    fn subdivisions_are_capped() {
+        assert_eq!(icosphere_triangle_count(9), icosphere_triangle_count(MAX_SUBDIVISIONS));
+        let (_, faces) = icosphere_geometry(1.0, MAX_SUBDIVISIONS + 1);
+        assert_eq!(faces.len() as u32, icosphere_triangle_count(MAX_SUBDIVISIONS));
+    }
+}
@@ -0,0 +1,152 @@
+// palette.rs - Color palettes for the spawned shapes (`--palette`): pure
+// functions from a position in the palette and a batch id to a color.
+
+use bevy::prelude::*;
+use std::fmt;
+use std::str::FromStr;
+
+/// Viridis key colors (sRGB), interpolated linearly between stops.
+const VIRIDIS: [[f32; 3]; 5] = [
+    [0.267, 0.005, 0.329],
+    [0.231, 0.322, 0.545],
+    [0.129, 0.569, 0.549],
+    [0.369, 0.788, 0.384],
+    [0.992, 0.906, 0.145],
+];
+
+/// Fractional part of the golden ratio: consecutive multiples stay far apart
+/// on the hue circle.
+const GOLDEN_FRACTION: f32 = 0.618_034;
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub enum Palette {
+    /// Saturated HSL hue sweep.
+    #[default]
+    HueSweep,
+    Viridis,
+    /// The hue sweep at high lightness and low saturation.
+    Pastel,
+    /// One hue (in degrees); only the lightness varies.
+    Monochrome { hue: f32 },
+    /// One distinct hue per batch, ignoring the palette position.
+    PerBatch,
+}
+
+impl Palette {
+    /// The color at `t` in [0, 1] for shapes of `batch`.
+    pub fn color(self, t: f32, batch: u32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Palette::HueSweep => Color::hsl(t * 360.0, 0.8, 0.5),
+            Palette::Viridis => {
+                let scaled = t * (VIRIDIS.len() - 1) as f32;
+                let stop = (scaled as usize).min(VIRIDIS.len() - 2);
+                let [r, g, b] = Vec3::from(VIRIDIS[stop])
+                    .lerp(Vec3::from(VIRIDIS[stop + 1]), scaled - stop as f32)
+                    .to_array();
+                Color::srgb(r, g, b)
+            }
+            Palette::Pastel => Color::hsl(t * 360.0, 0.6, 0.8),
+            Palette::Monochrome { hue } => Color::hsl(hue, 0.8, 0.25 + 0.5 * t),
+            Palette::PerBatch => Color::hsl((batch as f32 * GOLDEN_FRACTION).fract() * 360.0, 0.8, 0.5),
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once(':') {
+            Some(("monochrome", hue)) => hue
+                .parse::<f32>()
+                .map(|hue| Palette::Monochrome { hue: hue.rem_euclid(360.0) })
+                .map_err(|_| format!("Invalid monochrome hue: {} (expected degrees)", hue)),
+            _ => match value {
+                "hue" => Ok(Palette::HueSweep),
+                "viridis" => Ok(Palette::Viridis),
+                "pastel" => Ok(Palette::Pastel),
+                "monochrome" => Ok(Palette::Monochrome { hue: 200.0 }),
+                "per-batch" => Ok(Palette::PerBatch),
+                _ => Err(format!(
+                    "Unknown palette: {} (expected hue, viridis, pastel, monochrome[:HUE] or per-batch)",
+                    value
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Palette::HueSweep => write!(f, "hue"),
+            Palette::Viridis => write!(f, "viridis"),
+            Palette::Pastel => write!(f, "pastel"),
+            Palette::Monochrome { hue } => write!(f, "monochrome:{}", hue),
+            Palette::PerBatch => write!(f, "per-batch"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PALETTES: [Palette; 5] = [
+        Palette::HueSweep,
+        Palette::Viridis,
+        Palette::Pastel,
+        Palette::Monochrome { hue: 120.0 },
+        Palette::PerBatch,
+    ];
+
+    #[test]
+    fn palettes_produce_valid_colors() {
+        for palette in PALETTES {
+            for step in 0..=20 {
+                let color = palette.color(step as f32 / 20.0, step).to_srgba();
+                for channel in [color.red, color.green, color.blue] {
+                    assert!((0.0..=1.0).contains(&channel), "{} gave {:?}", palette, color);
+                }
+                assert_eq!(color.alpha, 1.0);
+            }
+            // Out-of-range positions are clamped
+            assert_eq!(palette.color(-1.0, 3), palette.color(0.0, 3));
+            assert_eq!(palette.color(2.0, 3), palette.color(1.0, 3));
+        }
+    }
+
+    #[test]
+    fn monochrome_varies_only_lightness() {
+        let palette = Palette::Monochrome { hue: 120.0 };
+        let colors: Vec<Hsla> = (0..=10).map(|step| Hsla::from(palette.color(step as f32 / 10.0, step))).collect();
+        for color in &colors {
+            assert!((color.hue - 120.0).abs() < 1e-3);
+            assert!((color.saturation - 0.8).abs() < 1e-6);
+        }
+        assert!(colors.windows(2).all(|pair| pair[1].lightness > pair[0].lightness));
+    }
+
+    #[test]
+    fn per_batch_hues_are_distinct() {
+        let hues: Vec<f32> = (1..=12).map(|batch| Hsla::from(Palette::PerBatch.color(0.5, batch)).hue).collect();
+        for (i, a) in hues.iter().enumerate() {
+            for b in &hues[i + 1..] {
+                let distance = (a - b).abs().min(360.0 - (a - b).abs());
+                assert!(distance > 10.0, "hues {} and {} are too close", a, b);
+            }
+        }
+        // The palette position doesn't matter
+        assert_eq!(Palette::PerBatch.color(0.0, 5), Palette::PerBatch.color(1.0, 5));
+    }
+
+    #[test]
+    fn palettes_round_trip_through_strings() {
+        for palette in PALETTES {
+            assert_eq!(palette.to_string().parse::<Palette>(), Ok(palette));
+        }
+        assert_eq!("monochrome:-40".parse::<Palette>(), Ok(Palette::Monochrome { hue: 320.0 }));
+        assert!("rainbow".parse::<Palette>().is_err());
+    }
+}
@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+use bevy_test::{StressConfig, StressTestPlugin};
+use std::time::{Duration, Instant};
+
+#[test]
+fn plugin_runs_headless_under_minimal_plugins() {
+    let config = StressConfig::parse(["--duration", "0.2"].map(String::from)).unwrap();
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StressTestPlugin::from(config)));
+
+    // Systems that need rendering, input or windows are skipped, not fatal;
+    // the --duration countdown still ends the run
+    let started = Instant::now();
+    let exit = loop {
+        app.update();
+        if let Some(exit) = app.should_exit() {
+            break exit;
+        }
+        assert!(started.elapsed() < Duration::from_secs(10), "--duration never exited");
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    assert_eq!(exit, AppExit::Success);
+    assert!(started.elapsed() >= Duration::from_millis(200));
+}